@@ -0,0 +1,21 @@
+# bento shell integration for bash
+#
+# Add this to ~/.bashrc:
+#
+#   eval "$(bento init bash)"
+#
+# Ctrl-G opens the picker, seeded with the word before the cursor, and
+# replaces that word with the chosen command.
+
+__bento_widget() {
+  local left=${READLINE_LINE:0:READLINE_POINT}
+  local word=${left##*[[:space:]]}
+  local selected
+  selected=$(bento pick -- "$word") || return
+  left=${left%"$word"}$selected
+  READLINE_LINE=$left${READLINE_LINE:READLINE_POINT}
+  READLINE_POINT=${#left}
+}
+
+bind -m emacs-standard -x '"\C-g": __bento_widget'
+bind -m vi-insert -x '"\C-g": __bento_widget'
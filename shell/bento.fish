@@ -0,0 +1,17 @@
+# bento shell integration for fish
+#
+# Add this to ~/.config/fish/config.fish:
+#
+#   bento init fish | source
+#
+# Ctrl-G opens the picker, seeded with the token under the cursor, and
+# replaces that token with the chosen command.
+
+function __bento_widget
+    set -l selected (bento pick -- (commandline -t))
+    and commandline -t -- $selected
+    commandline -f repaint
+end
+
+bind \cg __bento_widget
+bind -M insert \cg __bento_widget
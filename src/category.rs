@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use colored::Color;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::BentoCommand;
+
+/// The kind of source a command was found in.
+///
+/// Built-in sources each have their own variant; commands from custom sources
+/// use [`Category::Other`] with the name chosen by that source.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    Bin,
+    Homebrew,
+    Cask,
+    Pip,
+    Npm,
+    Yarn,
+    Cargo,
+    Go,
+    Alias,
+    Function,
+    Other(String),
+}
+
+static BUILTIN: [Category; 10] = [
+    Category::Bin,
+    Category::Homebrew,
+    Category::Cask,
+    Category::Pip,
+    Category::Npm,
+    Category::Yarn,
+    Category::Cargo,
+    Category::Go,
+    Category::Alias,
+    Category::Function,
+];
+
+impl Category {
+    /// Every built-in category, in the order they are listed in help text.
+    pub fn builtin() -> &'static [Category] {
+        &BUILTIN
+    }
+
+    /// The name used for this category on the command line and in output.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Category::Bin => "bin",
+            Category::Homebrew => "homebrew",
+            Category::Cask => "cask",
+            Category::Pip => "pip",
+            Category::Npm => "npm",
+            Category::Yarn => "yarn",
+            Category::Cargo => "cargo",
+            Category::Go => "go",
+            Category::Alias => "alias",
+            Category::Function => "function",
+            Category::Other(name) => name,
+        }
+    }
+
+    /// Human readable description of what the category contains.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Category::Bin => "Binary commands",
+            Category::Homebrew => "Homebrew formulae",
+            Category::Cask => "Homebrew casks",
+            Category::Pip => "Python packages",
+            Category::Npm => "NPM packages",
+            Category::Yarn => "Yarn packages",
+            Category::Cargo => "Rust packages",
+            Category::Go => "Go packages",
+            Category::Alias => "Shell aliases",
+            Category::Function => "Shell functions",
+            Category::Other(_) => "Custom commands",
+        }
+    }
+
+    /// Color used to print command names of this category.
+    pub fn color(&self) -> Color {
+        match self {
+            Category::Bin => Color::Blue,
+            Category::Homebrew => Color::Cyan,
+            Category::Cask => Color::BrightCyan,
+            Category::Pip => Color::Red,
+            Category::Npm => Color::Magenta,
+            Category::Yarn => Color::BrightMagenta,
+            Category::Cargo => Color::BrightRed,
+            Category::Go => Color::BrightBlue,
+            Category::Alias => Color::Yellow,
+            Category::Function => Color::Green,
+            Category::Other(_) => Color::White,
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Category {
+    fn from(name: &str) -> Self {
+        BUILTIN
+            .iter()
+            .find(|category| category.as_str() == name)
+            .cloned()
+            .unwrap_or_else(|| Category::Other(name.to_string()))
+    }
+}
+
+impl From<String> for Category {
+    fn from(name: String) -> Self {
+        Category::from(name.as_str())
+    }
+}
+
+/// Error returned when parsing an empty or malformed category name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCategoryError(String);
+
+impl fmt::Display for ParseCategoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid category name {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseCategoryError {}
+
+impl FromStr for Category {
+    type Err = ParseCategoryError;
+
+    /// Parse a category name case-insensitively. Unknown names become
+    /// [`Category::Other`]; names that are empty or contain whitespace or
+    /// commas are rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == ',') {
+            return Err(ParseCategoryError(s.to_string()));
+        }
+        Ok(Category::from(name.as_str()))
+    }
+}
+
+impl Serialize for Category {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Category {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Category::from)
+    }
+}
+
+/// Which categories to search: any of `include` (or all of them when it is
+/// empty), except those in `exclude`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryFilter {
+    pub include: Vec<Category>,
+    pub exclude: Vec<Category>,
+}
+
+impl CategoryFilter {
+    /// A filter that allows every category.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether commands in `category` are searched.
+    pub fn allows(&self, category: &Category) -> bool {
+        (self.include.is_empty() || self.include.contains(category))
+            && !self.exclude.contains(category)
+    }
+
+    /// Whether `cmd` was found in any allowed category.
+    ///
+    /// A merged command keeps every category it was found in; to leave out
+    /// the others, filter the entries with [`CategoryFilter::allows`] before
+    /// [`merge`](crate::merge) instead.
+    pub fn matches(&self, cmd: &BentoCommand) -> bool {
+        cmd.categories().any(|category| self.allows(category))
+    }
+}
+
+/// The colors categories are printed in: each category's
+/// [`Category::color`] unless it has been overridden.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    overrides: HashMap<Category, Color>,
+}
+
+impl Palette {
+    /// A palette with every category in its default color.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print `category` in `color` instead of its default.
+    pub fn set(&mut self, category: Category, color: Color) -> &mut Self {
+        self.overrides.insert(category, color);
+        self
+    }
+
+    /// The color `category` is printed in.
+    pub fn color(&self, category: &Category) -> Color {
+        self.overrides
+            .get(category)
+            .copied()
+            .unwrap_or_else(|| category.color())
+    }
+}
@@ -0,0 +1,404 @@
+//! The user's configuration file.
+//!
+//! Settings are read from `$BENTO_CONFIG` if it is set, and otherwise from
+//! `bento/config.toml` in `$XDG_CONFIG_HOME` (`~/.config` by default). Every
+//! setting is optional, and options given on the command line take
+//! precedence over the file.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use colored::Color;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::sources::{AliasSource, CustomSource, FunctionSource, home_dir};
+use crate::{Category, CategoryFilter, Palette, Registry};
+
+/// Written by `bento config edit` when there is no configuration file yet.
+pub const TEMPLATE: &str = r##"# bento configuration; every setting is optional.
+
+# Run only these sources (default: all of them)
+# sources = ["path", "alias", "function", "homebrew"]
+
+# Never run these sources
+# disable = ["go", "yarn"]
+
+# Search only these categories, or leave some out, unless filters are given
+# on the command line
+# categories = ["bin", "alias", "function"]
+# exclude = ["cask"]
+
+# How many results to show without --limit or --all
+# limit = 15
+
+# Hide commands whose names match any of these patterns; `*` matches any
+# run of characters and `?` a single one
+# ignore = ["_*", "*.dll"]
+
+# Seconds to wait for each source, and for particular sources
+# timeout = 10
+# [timeouts]
+# pip = 3
+
+# Aliases and functions are read from your shell's startup files and the
+# files they source. Also ask your shell, started interactively, for those
+# defined in ways that cannot be read, e.g. with eval; this runs your
+# startup files, which can be slow or print output
+# interactive_shell = false
+
+# Override the color of a category: black, red, green, yellow, blue, magenta,
+# cyan, white, their "bright " variants, or "#rrggbb"
+# [colors]
+# alias = "bright yellow"
+# bin = "#5f87ff"
+
+# Search your own tools: the executables in a directory, or the lines a
+# command prints, each `name` or `name<TAB>description`
+# [[source]]
+# name = "tools"
+# dir = "~/src/monorepo/tools"
+# color = "bright green"
+#
+# [[source]]
+# name = "plugins"
+# command = "acme plugins list"
+# category = "acme"
+"##;
+
+/// Settings loaded from the configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Sources to run, by name; every registered source when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// Sources never to run, by name.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
+    /// Categories searched when none are given on the command line.
+    #[serde(
+        deserialize_with = "parse_categories",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub categories: Vec<Category>,
+    /// Categories left out when no filters are given on the command line.
+    #[serde(
+        deserialize_with = "parse_categories",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub exclude: Vec<Category>,
+    /// How many search results to show by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Name patterns of commands to hide, with `*` and `?` wildcards.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+    /// Seconds to wait for sources without a timeout of their own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+    /// Whether the alias and function sources also run the user's shell
+    /// interactively, instead of only reading its startup files.
+    pub interactive_shell: bool,
+    /// Seconds to wait for particular sources, by source name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub timeouts: BTreeMap<String, f64>,
+    /// Colors to print categories in, by category name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub colors: BTreeMap<String, String>,
+    /// Custom sources, from `[[source]]` tables.
+    #[serde(rename = "source", skip_serializing_if = "Vec::is_empty")]
+    pub custom_sources: Vec<SourceConfig>,
+}
+
+/// Parse category names the way `--category` does, ignoring case and
+/// rejecting empty or malformed names.
+fn parse_categories<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Category>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|name| name.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// A custom source declared in a `[[source]]` table, listing either the
+/// executables in `dir` or the output of `command`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    pub name: String,
+    /// Category given to the source's commands; defaults to its name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Color to print the category in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Directory to list executables from; `~/` is the home directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Shell command printing `name` or `name<TAB>description` lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl SourceConfig {
+    /// The category the source's commands are given.
+    pub fn category(&self) -> Category {
+        self.category
+            .as_deref()
+            .unwrap_or(&self.name)
+            .parse()
+            .unwrap_or_else(|_| Category::Other(self.name.clone()))
+    }
+
+    /// The source described by this table, or `None` if it has neither a
+    /// `dir` nor a `command`.
+    pub fn source(&self) -> Option<CustomSource> {
+        if let Some(dir) = &self.dir {
+            let dir = match (dir.strip_prefix("~"), home_dir()) {
+                (Ok(rest), Some(home)) => home.join(rest),
+                _ => dir.clone(),
+            };
+            Some(CustomSource::dir(&self.name, self.category(), dir))
+        } else {
+            let command = self.command.as_deref()?;
+            Some(CustomSource::command(&self.name, self.category(), command))
+        }
+    }
+}
+
+/// Error returned when the configuration file cannot be read or is invalid.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => err.fmt(f),
+            ConfigError::Parse(err) => err.fmt(f),
+            ConfigError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Where the configuration file is read from: `$BENTO_CONFIG`, or
+    /// `bento/config.toml` in `$XDG_CONFIG_HOME` or `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("BENTO_CONFIG").filter(|path| !path.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_dir.join("bento").join("config.toml"))
+    }
+
+    /// Load the configuration at `path`. A missing file yields the defaults.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse(),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ConfigError::Io(err)),
+        }
+    }
+
+    /// Register the custom sources with `registry`, then apply the source
+    /// selection, shell and timeout settings to it.
+    ///
+    /// Returns the source names in [`Config::sources`] and
+    /// [`Config::disable`] that match no registered source.
+    pub fn configure(&self, registry: &mut Registry) -> Vec<String> {
+        for source in self.custom_sources.iter().filter_map(SourceConfig::source) {
+            registry.register(source);
+        }
+        let registered: Vec<String> = registry
+            .sources()
+            .map(|source| source.name().to_string())
+            .collect();
+        let unknown = self
+            .sources
+            .iter()
+            .chain(&self.disable)
+            .filter(|name| !registered.contains(name))
+            .cloned()
+            .collect();
+
+        for name in &registered {
+            if (!self.sources.is_empty() && !self.sources.contains(name))
+                || self.disable.contains(name)
+            {
+                registry.disable(name);
+            }
+        }
+        if self.interactive_shell {
+            registry.replace(AliasSource::new().with_interactive_shell(true));
+            registry.replace(FunctionSource::new().with_interactive_shell(true));
+        }
+        if let Some(seconds) = self.timeout {
+            registry.set_timeout(Duration::from_secs_f64(seconds));
+        }
+        for (name, &seconds) in &self.timeouts {
+            registry.set_source_timeout(name, Duration::from_secs_f64(seconds));
+        }
+        unknown
+    }
+
+    /// The default category filter.
+    pub fn filter(&self) -> CategoryFilter {
+        CategoryFilter {
+            include: self.categories.clone(),
+            exclude: self.exclude.clone(),
+        }
+    }
+
+    /// The category colors, with any overrides applied.
+    pub fn palette(&self) -> Palette {
+        let mut palette = Palette::new();
+        for (category, color) in &self.colors {
+            if let (Ok(category), Some(color)) = (category.parse(), parse_color(color)) {
+                palette.set(category, color);
+            }
+        }
+        for source in &self.custom_sources {
+            if let Some(color) = source.color.as_deref().and_then(parse_color) {
+                palette.set(source.category(), color);
+            }
+        }
+        palette
+    }
+
+    /// Whether commands named `name` are hidden by an [`Config::ignore`]
+    /// pattern.
+    pub fn is_ignored(&self, name: &str) -> bool {
+        self.ignore.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Check the settings TOML cannot express.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let check_timeout = |key: &str, seconds: f64| {
+            if Duration::try_from_secs_f64(seconds).is_ok() {
+                Ok(())
+            } else {
+                Err(ConfigError::Invalid(format!(
+                    "{key} must be a number of seconds, not {seconds}"
+                )))
+            }
+        };
+        if let Some(seconds) = self.timeout {
+            check_timeout("timeout", seconds)?;
+        }
+        for (name, &seconds) in &self.timeouts {
+            check_timeout(&format!("timeouts.{name}"), seconds)?;
+        }
+        for (category, color) in &self.colors {
+            if let Err(err) = category.parse::<Category>() {
+                return Err(ConfigError::Invalid(format!("colors: {err}")));
+            }
+            if parse_color(color).is_none() {
+                return Err(ConfigError::Invalid(format!(
+                    "colors.{category}: unknown color {color:?}"
+                )));
+            }
+        }
+        let builtin = Registry::builtin();
+        let mut names: Vec<&str> = builtin.sources().map(|source| source.name()).collect();
+        for source in &self.custom_sources {
+            let name = source.name.as_str();
+            let invalid =
+                |message: &str| Err(ConfigError::Invalid(format!("source {name:?}: {message}")));
+            if name.trim().is_empty() {
+                return Err(ConfigError::Invalid("source: name is empty".to_string()));
+            }
+            if names.contains(&name) {
+                return invalid("another source has this name");
+            }
+            names.push(name);
+            if source.dir.is_some() == source.command.is_some() {
+                return invalid("needs either a dir or a command");
+            }
+            if let Some(category) = &source.category
+                && let Err(err) = category.parse::<Category>()
+            {
+                return invalid(&err.to_string());
+            }
+            if source.category.is_none() && source.name.parse::<Category>().is_err() {
+                return invalid("needs a category, since its name is not a valid one");
+            }
+            if let Some(color) = &source.color
+                && parse_color(color).is_none()
+            {
+                return invalid(&format!("unknown color {color:?}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let config: Config = toml::from_str(text).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Parse a color name such as `bright blue` or `bright_blue`, or a
+/// `#rrggbb` hex code.
+fn parse_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::TrueColor {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    color.trim().replace(['_', '-'], " ").parse().ok()
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters
+/// and `?` matches exactly one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has swallowed
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, swallowed)) => {
+                    p = star + 1;
+                    n = swallowed + 1;
+                    backtrack = Some((star, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,109 @@
+//! Checks of the environment bento reads commands from, run by
+//! `bento doctor`.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+
+use crate::Context;
+
+/// Something wrong with an entry of `PATH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathProblem {
+    /// The entry is empty, which the shell treats as the current directory.
+    Empty,
+    /// The directory does not exist.
+    Missing,
+    /// The entry exists but is not a directory.
+    NotADirectory,
+    /// The same directory appears earlier in `PATH`.
+    Duplicate,
+    /// Anyone can add executables to the directory.
+    WorldWritable,
+}
+
+impl fmt::Display for PathProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PathProblem::Empty => "empty entry, searched as the current directory",
+            PathProblem::Missing => "does not exist",
+            PathProblem::NotADirectory => "not a directory",
+            PathProblem::Duplicate => "listed more than once",
+            PathProblem::WorldWritable => "writable by every user",
+        })
+    }
+}
+
+/// A problem with one entry of `PATH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathIssue {
+    pub entry: String,
+    pub problem: PathProblem,
+}
+
+/// The problems with the entries of `path`, a colon-separated list of
+/// directories like `PATH`, in the order they appear.
+pub fn check_path(path: &str) -> Vec<PathIssue> {
+    let mut seen = HashSet::new();
+    let mut issues = Vec::new();
+    for entry in path.split(':') {
+        let problem = if entry.is_empty() {
+            Some(PathProblem::Empty)
+        } else if !seen.insert(normalize(entry)) {
+            Some(PathProblem::Duplicate)
+        } else {
+            match fs::metadata(entry) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Some(PathProblem::Missing),
+                Err(_) => None,
+                Ok(meta) if !meta.is_dir() => Some(PathProblem::NotADirectory),
+                Ok(meta) if meta.permissions().mode() & 0o002 != 0 => {
+                    Some(PathProblem::WorldWritable)
+                }
+                Ok(_) => None,
+            }
+        };
+        if let Some(problem) = problem {
+            issues.push(PathIssue {
+                entry: entry.to_string(),
+                problem,
+            });
+        }
+    }
+    issues
+}
+
+/// `entry` without trailing slashes, so `/bin/` and `/bin` are the same.
+fn normalize(entry: &str) -> &str {
+    match entry.trim_end_matches('/') {
+        "" => "/",
+        trimmed => trimmed,
+    }
+}
+
+/// Check that `shell` can be started interactively, as the alias and
+/// function sources do when asked to run it.
+///
+/// Returns what went wrong if it could not be spawned, exited unsuccessfully
+/// or did not finish before `ctx`'s deadline.
+pub fn check_shell(shell: &str, ctx: &Context) -> Result<(), String> {
+    match ctx.output(shell, &["-i", "-c", "true"]) {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let mut message = match output.status.code() {
+                Some(code) => format!("exited with code {code}"),
+                None => "was killed by a signal".to_string(),
+            };
+            if let Some(line) = stderr.lines().find(|line| !line.trim().is_empty()) {
+                message = format!("{message}: {}", line.trim());
+            }
+            Err(message)
+        }
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => {
+            Err("timed out while reading its startup files".to_string())
+        }
+        Err(err) => Err(err.to_string()),
+    }
+}
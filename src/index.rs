@@ -0,0 +1,186 @@
+//! Persistent on-disk index of collected commands.
+//!
+//! Each source's commands are stored together with a [`Fingerprint`] of the
+//! files and directories it watches and of its settings. An entry is reused until the fingerprint
+//! changes or it is older than the index's time-to-live.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::BentoCommand;
+
+/// How long an entry is reused when none of its watched paths change.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Bumped whenever the on-disk format changes, discarding older indexes.
+const VERSION: u32 = 5;
+
+/// Modification times of the paths a source watches, along with the
+/// settings it was collected with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    paths: Vec<(PathBuf, Option<u64>)>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    settings: String,
+}
+
+impl Fingerprint {
+    /// Record the current modification time of each path.
+    ///
+    /// Paths that do not exist are recorded too, so creating them later also
+    /// changes the fingerprint.
+    pub fn of(paths: &[PathBuf]) -> Self {
+        Self {
+            paths: paths
+                .iter()
+                .map(|path| (path.clone(), modified_nanos(path)))
+                .collect(),
+            settings: String::new(),
+        }
+    }
+
+    /// Also record `settings`, so changing them changes the fingerprint.
+    pub fn with_settings(mut self, settings: impl Into<String>) -> Self {
+        self.settings = settings.into();
+        self
+    }
+}
+
+fn modified_nanos(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+    let since_epoch = modified.ok()?.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_nanos()).ok()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    collected_at: u64,
+    fingerprint: Fingerprint,
+    commands: Vec<BentoCommand>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u32,
+    sources: HashMap<String, Entry>,
+}
+
+/// Cached commands for each source, keyed by source name.
+#[derive(Debug, Clone)]
+pub struct Index {
+    entries: HashMap<String, Entry>,
+    ttl: Duration,
+    changed: bool,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl: DEFAULT_TTL,
+            changed: false,
+        }
+    }
+}
+
+impl Index {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index file under the XDG cache directory, usually
+    /// `~/.cache/bento/index.json`.
+    pub fn default_path() -> Option<PathBuf> {
+        let cache_dir = env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(cache_dir.join("bento").join("index.json"))
+    }
+
+    /// Load an index from `path`.
+    ///
+    /// A missing, unreadable or outdated file yields an empty index, since it
+    /// can always be rebuilt.
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<IndexFile>(&bytes).ok())
+            .filter(|file| file.version == VERSION)
+            .map(|file| file.sources)
+            .unwrap_or_default();
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Write the index to `path`, creating parent directories as needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = IndexFile {
+            version: VERSION,
+            sources: self.entries.clone(),
+        };
+        let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+
+        // Write then rename so concurrent runs never read a partial file
+        let tmp = path.with_extension(format!("tmp.{}", std::process::id()));
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Set how long entries stay valid when none of their watched paths change.
+    pub fn set_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The cached commands of `source`, if its fingerprint still matches and
+    /// the entry has not expired.
+    pub fn get(&self, source: &str, fingerprint: &Fingerprint) -> Option<&[BentoCommand]> {
+        let entry = self.entries.get(source)?;
+        let age = now().saturating_sub(entry.collected_at);
+        (entry.fingerprint == *fingerprint && age < self.ttl.as_secs())
+            .then_some(entry.commands.as_slice())
+    }
+
+    /// Store freshly collected commands for `source`.
+    pub fn insert(&mut self, source: &str, fingerprint: Fingerprint, commands: Vec<BentoCommand>) {
+        let entry = Entry {
+            collected_at: now(),
+            fingerprint,
+            commands,
+        };
+        self.entries.insert(source.to_string(), entry);
+        self.changed = true;
+    }
+
+    /// Forget every cached entry.
+    pub fn clear(&mut self) {
+        self.changed |= !self.entries.is_empty();
+        self.entries.clear();
+    }
+
+    /// Whether entries were stored or forgotten since the index was created
+    /// or loaded, i.e. whether it needs saving.
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,55 @@
+//! Shell integration scripts printed by `bento init <shell>`.
+//!
+//! Each script defines a widget bound to Ctrl-G that runs `bento pick` with
+//! the word before the cursor and puts the chosen command in its place.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A shell that `bento init` can print an integration script for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// Every supported shell, in the order they are listed in help text.
+    pub const ALL: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+    /// The name used to select this shell on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+
+    /// The integration script, meant to be evaluated by the shell's rc file.
+    pub fn script(&self) -> &'static str {
+        match self {
+            Shell::Bash => include_str!("../shell/bento.bash"),
+            Shell::Zsh => include_str!("../shell/bento.zsh"),
+            Shell::Fish => include_str!("../shell/bento.fish"),
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Shell::ALL
+            .into_iter()
+            .find(|shell| shell.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unsupported shell {s:?}"))
+    }
+}
@@ -1,285 +1,140 @@
-use std::env;
-use std::fs;
-use std::os::unix::fs::PermissionsExt;
-use std::process::Command;
-
-#[derive(Debug, Clone)]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+mod category;
+pub mod config;
+pub mod doctor;
+pub mod index;
+pub mod init;
+mod matcher;
+mod merge;
+pub mod output;
+pub mod picker;
+pub mod resolve;
+mod search;
+pub mod sources;
+
+pub use category::{Category, CategoryFilter, Palette, ParseCategoryError};
+pub use config::Config;
+pub use index::Index;
+pub use matcher::{Field, Match, fuzzy_match, fuzzy_match_positions, match_command};
+pub use merge::merge;
+pub use search::{SearchMode, SearchQuery, SearchResult, SearchResults, Searcher};
+pub use sources::{
+    Collection, CommandRunner, Context, Registry, Source, SourceReport, SourceStatus,
+};
+
+/// A command found by a [`Source`].
+///
+/// Build one with [`BentoCommand::new`] and the `with_*` methods; fields may
+/// be added in any release.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct BentoCommand {
     pub name: String,
-    pub category: String,
+    pub category: Category,
+    /// Other categories the same name was found in, after [`merge`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_in: Vec<Category>,
+    /// A one-line summary of what the command does, e.g. from its man page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Where the command was found, for executables on `PATH`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// The file [`BentoCommand::path`] resolves to, when it is a symlink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
+    /// Other places on `PATH` the same name was found, after [`merge`], in
+    /// the order the shell looks them up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub also_at: Vec<Location>,
+    /// What an alias expands to, or a function's source code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition: Option<String>,
+    /// Executables a package installs, for packages that report them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provides: Vec<String>,
+    /// Packages of another name that installed the command, after [`merge`],
+    /// e.g. `ripgrep` for `rg`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
 }
 
-impl BentoCommand {
-    pub fn new(name: String, category: String) -> Self {
-        Self { name, category }
-    }
+/// A place a command was found on `PATH`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    pub path: PathBuf,
+    /// The file `path` resolves to, when it is a symlink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<PathBuf>,
 }
 
-pub fn get_commands() -> Vec<BentoCommand> {
-    let mut commands = Vec::new();
-
-    // Bin commands from PATH
-    if let Ok(path_var) = env::var("PATH") {
-        for dir in path_var.split(':') {
-            if let Ok(entries) = fs::read_dir(dir) {
-                for entry in entries.filter_map(Result::ok) {
-                    if let Ok(metadata) = entry.metadata() {
-                        if metadata.is_file() && (metadata.permissions().mode() & 0o111 != 0) {
-                            if let Ok(name) = entry.file_name().into_string() {
-                                commands.push(BentoCommand {
-                                    name,
-                                    category: "bin".to_string(),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Homebrew packages
-    if let Ok(output) = Command::new("brew").arg("list").arg("--formula").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            let name = line.trim();
-            if !name.is_empty() {
-                commands.push(BentoCommand {
-                    name: name.to_string(),
-                    category: "homebrew".to_string(),
-                });
-            }
-        }
-    }
-
-    // Homebrew casks
-    if let Ok(output) = Command::new("brew").arg("list").arg("--cask").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            let name = line.trim();
-            if !name.is_empty() {
-                commands.push(BentoCommand {
-                    name: name.to_string(),
-                    category: "cask".to_string(),
-                });
-            }
-        }
-    }
-
-    // Python packages (pip)
-    if let Ok(output) = Command::new("pip")
-        .arg("list")
-        .arg("--format=freeze")
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if let Some(name) = line.split("==").next() {
-                commands.push(BentoCommand {
-                    name: name.to_string(),
-                    category: "pip".to_string(),
-                });
-            }
+impl BentoCommand {
+    pub fn new(name: impl Into<String>, category: impl Into<Category>) -> Self {
+        Self {
+            name: name.into(),
+            category: category.into(),
+            also_in: Vec::new(),
+            description: None,
+            path: None,
+            link_target: None,
+            also_at: Vec::new(),
+            definition: None,
+            provides: Vec::new(),
+            packages: Vec::new(),
         }
     }
 
-    // Node packages (npm global)
-    if let Ok(output) = Command::new("npm")
-        .arg("list")
-        .arg("-g")
-        .arg("--depth=0")
-        .arg("--parseable")
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if let Some(name) = line.split('/').last() {
-                if name != "lib" && !name.is_empty() {
-                    commands.push(BentoCommand {
-                        name: name.to_string(),
-                        category: "npm".to_string(),
-                    });
-                }
-            }
-        }
+    /// The command's category followed by any others it was merged from.
+    pub fn categories(&self) -> impl Iterator<Item = &Category> {
+        std::iter::once(&self.category).chain(&self.also_in)
     }
 
-    // Yarn global packages
-    if let Ok(output) = Command::new("yarn").arg("global").arg("list").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if line.starts_with("info ") && line.contains("@") {
-                if let Some(name) = line.split("@").next() {
-                    let clean_name = name.trim_start_matches("info ");
-                    commands.push(BentoCommand {
-                        name: clean_name.to_string(),
-                        category: "yarn".to_string(),
-                    });
-                }
-            }
-        }
+    /// Whether the command was found in `category`.
+    pub fn is_in(&self, category: &Category) -> bool {
+        self.categories().any(|c| c == category)
     }
 
-    // Rust packages (cargo)
-    if let Ok(output) = Command::new("cargo").arg("install").arg("--list").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if !line.starts_with(" ") && line.contains(" v") {
-                if let Some(name) = line.split(" v").next() {
-                    commands.push(BentoCommand {
-                        name: name.to_string(),
-                        category: "cargo".to_string(),
-                    });
-                }
-            }
-        }
+    /// Set the command's description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
     }
 
-    // Go packages
-    if let Ok(output) = Command::new("go").arg("list").arg("-m").arg("all").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if let Some(name) = line.split_whitespace().next() {
-                if name.contains("/") {
-                    if let Some(pkg_name) = name.split("/").last() {
-                        commands.push(BentoCommand {
-                            name: pkg_name.to_string(),
-                            category: "go".to_string(),
-                        });
-                    }
-                }
-            }
-        }
+    /// Set where the command was found, and what it links to if it is a
+    /// symlink.
+    pub fn with_path(mut self, path: impl Into<PathBuf>, link_target: Option<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self.link_target = link_target;
+        self
     }
 
-    // Aliases - try multiple shell methods
-    for cmd in ["zsh -c 'alias'", "bash -c 'alias'", "sh -c 'alias'"] {
-        if let Ok(output) = Command::new("sh").arg("-c").arg(cmd).output() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                if line.contains('=') && !line.trim().is_empty() {
-                    if let Some(name) = line.split('=').next() {
-                        let clean_name = name.trim_start_matches("alias ").trim();
-                        if !clean_name.is_empty() && !clean_name.starts_with('-') {
-                            commands.push(BentoCommand {
-                                name: clean_name.to_string(),
-                                category: "alias".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            if !stdout.is_empty() {
-                break;
-            }
-        }
+    /// Set what the command expands to, e.g. an alias's replacement text.
+    pub fn with_definition(mut self, definition: impl Into<String>) -> Self {
+        self.definition = Some(definition.into());
+        self
     }
 
-    // Functions - try multiple methods
-    for cmd in [
-        "zsh -c 'print -l ${(k)functions}'",
-        "bash -c 'declare -F | cut -d\" \" -f3'",
-        "zsh -c 'functions | grep \"^[a-zA-Z]\" | cut -d\" \" -f1'",
-    ] {
-        if let Ok(output) = Command::new("sh").arg("-c").arg(cmd).output() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                let name = line.trim();
-                if !name.is_empty() && !name.contains(' ') && !name.starts_with('_') {
-                    commands.push(BentoCommand {
-                        name: name.to_string(),
-                        category: "function".to_string(),
-                    });
-                }
-            }
-            if !stdout.is_empty() {
-                break;
-            }
-        }
+    /// A definition short enough to show next to the name, such as an
+    /// alias's expansion.
+    pub fn expansion(&self) -> Option<&str> {
+        self.definition
+            .as_deref()
+            .filter(|definition| !definition.contains('\n'))
     }
 
-    // Direct shell execution for current shell
-    if let Ok(shell) = env::var("SHELL") {
-        // Get aliases from current shell
-        if let Ok(output) = Command::new(&shell)
-            .arg("-i")
-            .arg("-c")
-            .arg("alias")
-            .output()
-        {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                if line.contains('=') && !line.trim().is_empty() {
-                    if let Some(name) = line.split('=').next() {
-                        let clean_name = name
-                            .trim_start_matches("alias ")
-                            .trim()
-                            .trim_matches('\'')
-                            .trim_matches('"');
-                        if !clean_name.is_empty()
-                            && clean_name
-                                .chars()
-                                .all(|c| c.is_alphanumeric() || "_-~.".contains(c))
-                        {
-                            commands.push(BentoCommand {
-                                name: clean_name.to_string(),
-                                category: "alias".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-        }
-
-        // Get functions from current shell
-        if shell.contains("zsh") {
-            if let Ok(output) = Command::new(&shell)
-                .arg("-i")
-                .arg("-c")
-                .arg("print -l ${(k)functions}")
-                .output()
-            {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                for line in stdout.lines() {
-                    let name = line.trim();
-                    if !name.is_empty()
-                        && !name.starts_with('_')
-                        && name
-                            .chars()
-                            .all(|c| c.is_alphanumeric() || "_-".contains(c))
-                    {
-                        commands.push(BentoCommand {
-                            name: name.to_string(),
-                            category: "function".to_string(),
-                        });
-                    }
-                }
-            }
-        }
+    /// Set the executables a package installs.
+    pub fn with_provides(mut self, provides: Vec<String>) -> Self {
+        self.provides = provides;
+        self
     }
-
-    commands
 }
 
-pub fn fuzzy_match(query: &str, target: &str) -> usize {
-    let query = query.to_lowercase();
-    let target = target.to_lowercase();
-
-    if target.contains(&query) {
-        return query.len() * 2;
-    }
-
-    let mut score = 0;
-    let mut target_chars = target.chars().peekable();
-
-    for q_char in query.chars() {
-        while let Some(&t_char) = target_chars.peek() {
-            target_chars.next();
-            if q_char == t_char {
-                score += 1;
-                break;
-            }
-        }
-    }
-    score
+/// Collect commands from every built-in source.
+///
+/// Use a [`Registry`] directly to add custom sources or disable built-in ones.
+pub fn get_commands() -> Vec<BentoCommand> {
+    Registry::builtin().collect()
 }
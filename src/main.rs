@@ -1,133 +1,424 @@
-use bento::{fuzzy_match, get_commands};
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind, IsTerminal, Write};
+use std::path::Path;
+use std::process::{self, Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use bento::config::{self, Config};
+use bento::doctor::{self, PathProblem};
+use bento::init::Shell;
+use bento::output::{self, Format, Row};
+use bento::resolve;
+use bento::{
+    BentoCommand, Category, CategoryFilter, Context, Index, Palette, Registry, SearchMode,
+    SearchQuery, Searcher, SourceReport, SourceStatus, picker,
+};
+use clap::builder::PossibleValuesParser;
 use clap::{Arg, Command as ClapCommand};
 use colored::*;
+use crossterm::terminal;
+
+/// How many search results are shown without --limit or --all.
+const DEFAULT_LIMIT: usize = 15;
 
 fn main() {
-    let matches = ClapCommand::new("bento")
+    let mut cli = ClapCommand::new("bento")
         .about("Command organizer")
         .arg(Arg::new("query").help("Command to search for"))
         .arg(
-            Arg::new("alias")
-                .long("alias")
-                .help("Search aliases only")
-                .action(clap::ArgAction::SetTrue),
+            Arg::new("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("Give up on sources that take longer than this")
+                .value_parser(parse_timeout),
         )
         .arg(
-            Arg::new("function")
-                .long("function")
-                .help("Search functions only")
+            Arg::new("refresh")
+                .long("refresh")
+                .help("Ignore the command index and rebuild it from every source")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("bin")
-                .long("bin")
-                .help("Search bin commands only")
-                .action(clap::ArgAction::SetTrue),
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("Print results as colored text or in a machine-readable format")
+                .value_parser(PossibleValuesParser::new(
+                    ["text"]
+                        .into_iter()
+                        .chain(Format::ALL.iter().map(Format::as_str)),
+                ))
+                .default_value("text"),
         )
         .arg(
-            Arg::new("homebrew")
-                .long("homebrew")
-                .help("Search homebrew commands only")
+            Arg::new("describe")
+                .short('d')
+                .long("describe")
+                .help("Match descriptions and package summaries as well as names")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("pip")
-                .long("pip")
-                .help("Search pip packages only")
+            Arg::new("long")
+                .short('l')
+                .long("long")
+                .help("Show where each binary was found and what it links to")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("npm")
-                .long("npm")
-                .help("Search npm packages only")
-                .action(clap::ArgAction::SetTrue),
+            Arg::new("category")
+                .short('c')
+                .long("category")
+                .value_name("CATEGORIES")
+                .help("Search only these comma-separated categories, including custom ones")
+                .value_parser(str::parse::<Category>)
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("exclude")
+                .short('x')
+                .long("exclude")
+                .value_name("CATEGORIES")
+                .help("Leave out these comma-separated categories")
+                .value_parser(str::parse::<Category>)
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append),
+        )
+        .arg(
+            Arg::new("limit")
+                .short('n')
+                .long("limit")
+                .value_name("N")
+                .help(format!("Show at most N results [default: {DEFAULT_LIMIT}]"))
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with("all"),
         )
         .arg(
-            Arg::new("cargo")
-                .long("cargo")
-                .help("Search cargo packages only")
+            Arg::new("all")
+                .short('a')
+                .long("all")
+                .help("Show every result")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("cask")
-                .long("cask")
-                .help("Search homebrew casks only")
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("Report how each source went, with timings and errors, on stderr")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("yarn")
-                .long("yarn")
-                .help("Search yarn packages only")
+            Arg::new("raw")
+                .long("raw")
+                .help("List every source's entry separately instead of merging them by name")
                 .action(clap::ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("go")
-                .long("go")
-                .help("Search go packages only")
+            Arg::new("interactive")
+                .short('i')
+                .long("interactive")
+                .help("Pick a command interactively and print it")
                 .action(clap::ArgAction::SetTrue),
         )
-        .get_matches();
+        .subcommand(
+            ClapCommand::new("pick")
+                .about("Pick a command interactively and print it")
+                .arg(Arg::new("query").help("Initial query")),
+        )
+        .subcommand(
+            ClapCommand::new("shadows")
+                .about("List commands hidden by another of the same name, and which one wins"),
+        )
+        .subcommand(
+            ClapCommand::new("which")
+                .about("Show every definition of a name in the order the shell resolves them")
+                .arg(Arg::new("name").required(true).help("Command name")),
+        )
+        .subcommand(
+            ClapCommand::new("doctor")
+                .about("Check every source, PATH and your shell, and report what is wrong"),
+        )
+        .subcommand(
+            ClapCommand::new("init")
+                .about("Print a script that binds Ctrl-G to the picker in your shell")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(PossibleValuesParser::new(
+                            Shell::ALL.iter().map(Shell::as_str),
+                        )),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("config")
+                .about("Show or edit the configuration file")
+                .subcommand_required(true)
+                .subcommand(
+                    ClapCommand::new("path").about("Print where the configuration is read from"),
+                )
+                .subcommand(
+                    ClapCommand::new("show")
+                        .about("Print the configuration in effect, including defaults"),
+                )
+                .subcommand(
+                    ClapCommand::new("edit")
+                        .about("Open the configuration file in $VISUAL or $EDITOR"),
+                ),
+        );
+    for category in Category::builtin() {
+        cli = cli.arg(
+            Arg::new(category.as_str())
+                .long(category.as_str())
+                .help(format!(
+                    "Search {} only",
+                    category.description().to_lowercase()
+                ))
+                .action(clap::ArgAction::SetTrue),
+        );
+    }
+    let matches = cli.get_matches();
 
-    let commands = get_commands();
+    if let Some(("init", sub)) = matches.subcommand() {
+        if let Some(shell) = sub
+            .get_one::<String>("shell")
+            .and_then(|shell| shell.parse::<Shell>().ok())
+        {
+            print!("{}", shell.script());
+        }
+        return;
+    }
+    let config_path = Config::default_path();
+    if let Some(("config", sub)) = matches.subcommand() {
+        let Some(path) = &config_path else {
+            eprintln!(
+                "{} no configuration path; set BENTO_CONFIG or HOME",
+                "error:".red().bold()
+            );
+            process::exit(2);
+        };
+        match sub.subcommand() {
+            Some(("path", _)) => println!("{}", path.display()),
+            Some(("show", _)) => show_config(path),
+            Some(("edit", _)) => edit_config(path),
+            _ => {}
+        }
+        return;
+    }
+    let config = config_path.as_deref().map(load_config).unwrap_or_default();
 
-    if let Some(query) = matches.get_one::<String>("query") {
-        let mut scored: Vec<_> = commands
-            .iter()
-            .filter(|cmd| {
-                if matches.get_flag("alias") {
-                    return cmd.category == "alias";
-                }
-                if matches.get_flag("function") {
-                    return cmd.category == "function";
-                }
-                if matches.get_flag("bin") {
-                    return cmd.category == "bin";
-                }
-                if matches.get_flag("homebrew") {
-                    return cmd.category == "homebrew";
-                }
-                if matches.get_flag("cask") {
-                    return cmd.category == "cask";
-                }
-                if matches.get_flag("pip") {
-                    return cmd.category == "pip";
-                }
-                if matches.get_flag("npm") {
-                    return cmd.category == "npm";
-                }
-                if matches.get_flag("yarn") {
-                    return cmd.category == "yarn";
-                }
-                if matches.get_flag("cargo") {
-                    return cmd.category == "cargo";
-                }
-                if matches.get_flag("go") {
-                    return cmd.category == "go";
-                }
-                true
+    let mut registry = Registry::builtin();
+    for name in config.configure(&mut registry) {
+        eprintln!(
+            "{} unknown source {name:?} in configuration",
+            "warning:".yellow().bold()
+        );
+    }
+    if let Some(&timeout) = matches.get_one::<Duration>("timeout") {
+        registry.set_timeout(timeout);
+    }
+    if let Some(("doctor", _)) = matches.subcommand() {
+        doctor(&registry, config.interactive_shell);
+        return;
+    }
+    let index_path = Index::default_path();
+    let mut index = match &index_path {
+        Some(path) if !matches.get_flag("refresh") => Index::load(path),
+        _ => Index::new(),
+    };
+    let collection = registry.collect_cached(&mut index);
+    if let Some(path) = &index_path
+        && index.is_changed()
+    {
+        // The index is only a cache, so failing to write it is not an error
+        let _ = index.save(path);
+    }
+    for name in &collection.timed_out {
+        eprintln!(
+            "{} {} timed out after {:.1}s",
+            "warning:".yellow().bold(),
+            name,
+            registry.timeout_for(name).as_secs_f64()
+        );
+    }
+    if matches.get_flag("verbose") {
+        print_reports(&collection.reports);
+    }
+    let reports = collection.reports;
+    // `which` explains every definition, even of ignored names
+    let all = collection.commands;
+    // `shadows` needs every definition; everything else lists each name once
+    // unless --raw is given
+    let raw: Vec<BentoCommand> = all
+        .iter()
+        .filter(|cmd| !config.is_ignored(&cmd.name))
+        .cloned()
+        .collect();
+    let merge = |rows: Vec<BentoCommand>| {
+        if matches.get_flag("raw") {
+            rows
+        } else {
+            bento::merge(rows)
+        }
+    };
+    let commands = merge(raw.clone());
+
+    let mut filter = CategoryFilter::new();
+    filter.include = Category::builtin()
+        .iter()
+        .filter(|category| matches.get_flag(category.as_str()))
+        .cloned()
+        .collect();
+    for (arg, categories) in [
+        ("category", &mut filter.include),
+        ("exclude", &mut filter.exclude),
+    ] {
+        for category in matches.get_many::<Category>(arg).into_iter().flatten() {
+            if !categories.contains(category) {
+                categories.push(category.clone());
+            }
+        }
+    }
+    // Filters on the command line replace the configured ones
+    if filter == CategoryFilter::new() {
+        filter = config.filter();
+    }
+    // Filter before merging, so that an entry only shows the categories it
+    // was found in that are searched, and their details
+    let searched = merge(
+        raw.iter()
+            .filter(|cmd| filter.allows(&cmd.category))
+            .cloned()
+            .collect(),
+    );
+    let palette = config.palette();
+    let query = matches.get_one::<String>("query");
+    let describe = matches.get_flag("describe");
+    let mode = if describe {
+        SearchMode::Descriptions
+    } else {
+        SearchMode::Names
+    };
+    let long = matches.get_flag("long");
+    let explicit_limit = matches.get_one::<usize>("limit").copied();
+    let limit = if matches.get_flag("all") {
+        None
+    } else {
+        Some(explicit_limit.or(config.limit).unwrap_or(DEFAULT_LIMIT))
+    };
+    let interactive = match matches.subcommand() {
+        Some(("pick", sub)) => Some(sub.get_one::<String>("query")),
+        _ => matches.get_flag("interactive").then_some(query),
+    };
+    let format = matches
+        .get_one::<String>("format")
+        .and_then(|format| format.parse::<Format>().ok());
+
+    if let Some(("shadows", _)) = matches.subcommand() {
+        if let Err(err) = print_shadows(&mut io::stdout().lock(), &raw, &palette)
+            && err.kind() != ErrorKind::BrokenPipe
+        {
+            eprintln!("{} {}", "error:".red().bold(), err);
+            process::exit(1);
+        }
+    } else if let Some(("which", sub)) = matches.subcommand() {
+        let name = sub.get_one::<String>("name").map(String::as_str);
+        if let Err(err) = print_which(
+            &mut io::stdout().lock(),
+            &all,
+            name.unwrap_or_default(),
+            &palette,
+        ) && err.kind() != ErrorKind::BrokenPipe
+        {
+            eprintln!("{} {}", "error:".red().bold(), err);
+            process::exit(1);
+        }
+    } else if let Some(query) = interactive {
+        let query = query.map(String::as_str).unwrap_or_default();
+        match picker::pick(&searched, query, &filter, describe, &palette) {
+            Ok(Some(cmd)) => println!("{}", cmd.name),
+            // Like fzf, exit with 130 when nothing was picked
+            Ok(None) => process::exit(130),
+            Err(err) => {
+                eprintln!("{} {}", "error:".red().bold(), err);
+                process::exit(2);
+            }
+        }
+    } else if let Some(format) = format {
+        // Without a query, export every command unless asked for fewer
+        let limit = if query.is_some() {
+            limit
+        } else {
+            explicit_limit
+        };
+        let search = SearchQuery::new(query.map(String::as_str).unwrap_or_default())
+            .with_filter(filter)
+            .with_limit(limit)
+            .with_mode(mode);
+        let rows: Vec<Row> = Searcher::new(&searched)
+            .search(&search)
+            .results
+            .into_iter()
+            .map(|result| Row {
+                command: result.command,
+                score: result.matched.score,
             })
-            .map(|cmd| (fuzzy_match(query, &cmd.name), cmd))
-            .filter(|(score, _)| *score > 0)
             .collect();
-
-        scored.sort_by(|a, b| b.0.cmp(&a.0));
-
-        for (_, cmd) in scored.into_iter().take(15) {
-            let color = match cmd.category.as_str() {
-                "alias" => cmd.name.yellow(),
-                "function" => cmd.name.green(),
-                "bin" => cmd.name.blue(),
-                "homebrew" => cmd.name.cyan(),
-                "cask" => cmd.name.bright_cyan(),
-                "pip" => cmd.name.red(),
-                "npm" => cmd.name.magenta(),
-                "yarn" => cmd.name.bright_magenta(),
-                "cargo" => cmd.name.bright_red(),
-                "go" => cmd.name.bright_blue(),
-                _ => cmd.name.white(),
-            };
-            println!("{} ({})", color, cmd.category.dimmed());
+        if let Err(err) = output::write_rows(&mut io::stdout().lock(), format, &rows)
+            && err.kind() != ErrorKind::BrokenPipe
+        {
+            eprintln!("{} {}", "error:".red().bold(), err);
+            process::exit(1);
         }
+    } else if let Some(query) = query {
+        let search = SearchQuery::new(query.as_str())
+            .with_filter(filter)
+            .with_limit(limit)
+            .with_mode(mode);
+        let found = Searcher::new(&searched).search(&search);
+        let mut out = String::new();
+        for result in &found.results {
+            let cmd = result.command;
+            let mut name = highlight(
+                &cmd.name,
+                &result.matched.positions,
+                palette.color(&cmd.category),
+            );
+            if let Some(expansion) = cmd.expansion() {
+                name = format!("{name} → {expansion}");
+            }
+            let categories: Vec<&str> = cmd.categories().map(Category::as_str).collect();
+            let categories = categories.join(", ");
+            match &cmd.description {
+                Some(description) => out.push_str(&format!(
+                    "{} ({}) {}\n",
+                    name,
+                    categories.dimmed(),
+                    format!("- {description}").dimmed()
+                )),
+                None => out.push_str(&format!("{} ({})\n", name, categories.dimmed())),
+            }
+            if long && let Some(path) = &cmd.path {
+                out.push_str(&format!(
+                    "    {}\n",
+                    location(path, cmd.link_target.as_deref())
+                ));
+                // Merged executables list every place they were found
+                for other in &cmd.also_at {
+                    let location = location(&other.path, other.link_target.as_deref());
+                    out.push_str(&format!("    {}\n", location.dimmed()));
+                }
+            }
+        }
+        let omitted = found.omitted();
+        if omitted > 0 {
+            let footer = format!(
+                "… {omitted} more {} (use --limit N or --all to see them)",
+                if omitted == 1 { "match" } else { "matches" }
+            );
+            out.push_str(&format!("{}\n", footer.dimmed()));
+        }
+        print_paged(&out);
     } else {
         println!("{}", "🍱 Bento - Command Organizer".bold().cyan());
         println!(
@@ -139,24 +430,26 @@ fn main() {
         println!("  bento --homebrew git    Search homebrew packages");
         println!("  bento --alias ls        Search aliases only");
         println!("  bento --pip django      Search pip packages");
+        println!("  bento -c alias,function Search several categories");
+        println!("  bento -x bin git        Search everything but binaries");
+        println!("  bento config edit       Set default filters, colors and sources");
+        println!("  bento doctor            Find out why a source or command is missing");
 
         println!("\n{}", "Filters:".bold());
-        println!("  --bin        Binary commands");
-        println!("  --homebrew   Homebrew formulae");
-        println!("  --cask       Homebrew casks");
-        println!("  --pip        Python packages");
-        println!("  --npm        NPM packages");
-        println!("  --yarn       Yarn packages");
-        println!("  --cargo      Rust packages");
-        println!("  --go         Go packages");
-        println!("  --alias      Shell aliases");
-        println!("  --function   Shell functions");
+        for category in Category::builtin() {
+            println!("  --{:<11}{}", category.as_str(), category.description());
+        }
 
         println!("\n{}", "Examples:".bold());
         println!("  bento git               # Find all git-related commands");
         println!("  bento --homebrew bat    # Search homebrew for 'bat'");
         println!("  bento --alias my        # Find aliases containing 'my'");
 
+        println!("\n{}", "Sources:".bold());
+        for report in &reports {
+            println!("  {}", report_line(report));
+        }
+
         let total: usize = commands.len();
         println!(
             "\n{} {} commands available across all sources",
@@ -165,3 +458,361 @@ fn main() {
         );
     }
 }
+
+/// `path`, and what it links to if it is a symlink.
+fn location(path: &Path, link_target: Option<&Path>) -> String {
+    match link_target {
+        Some(target) => format!("{} → {}", path.display(), target.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// Parse a `--timeout` value, a non-negative number of seconds.
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|err| format!("{err}"))?;
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("expected a number of seconds, not {value}"))
+}
+
+/// Print how each source went to stderr, with the full error output of
+/// those that failed.
+fn print_reports(reports: &[SourceReport]) {
+    for report in reports {
+        for line in report_lines(report, None) {
+            eprintln!("{line}");
+        }
+    }
+}
+
+/// How a source went and how long it took, followed by `version` if given
+/// and the rest of its error output if it failed.
+fn report_lines(report: &SourceReport, version: Option<&str>) -> Vec<String> {
+    let mut first = report_line(report);
+    match (&report.status, report.cached) {
+        (SourceStatus::NotInstalled, _) => {}
+        (_, true) => first = format!("{first} {}", "(cached)".dimmed()),
+        (_, false) => {
+            let elapsed = format!("({:.2}s)", report.elapsed.as_secs_f64());
+            first = format!("{first} {}", elapsed.dimmed());
+        }
+    }
+    if let Some(version) = version {
+        first = format!("{first}  {}", version.dimmed());
+    }
+    let mut lines = vec![first];
+    if let SourceStatus::Failed { stderr, .. } = &report.status {
+        for line in stderr
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+            .skip(1)
+        {
+            lines.push(format!("      {}", line.dimmed()));
+        }
+    }
+    lines
+}
+
+/// Collect every source afresh and check `PATH` and the user's shell,
+/// printing what was found. Exits with status 1 if anything is wrong.
+///
+/// The shell only has to start when `interactive_shell` is set.
+fn doctor(registry: &Registry, interactive_shell: bool) {
+    println!("{} {}", "bento".bold(), env!("CARGO_PKG_VERSION"));
+    let mut problems = 0;
+
+    println!("\n{}", "Sources:".bold());
+    let versions = registry.versions();
+    for report in registry.collect_report().reports {
+        if matches!(
+            report.status,
+            SourceStatus::Failed { .. } | SourceStatus::TimedOut
+        ) {
+            problems += 1;
+        }
+        let version = versions.get(&report.name).map(String::as_str);
+        for line in report_lines(&report, version) {
+            println!("  {line}");
+        }
+    }
+
+    println!("\n{}", "PATH:".bold());
+    let path = env::var("PATH").unwrap_or_default();
+    let issues = doctor::check_path(&path);
+    if issues.is_empty() {
+        match path.split(':').count() {
+            1 => println!("  {} 1 directory", "✓".green()),
+            count => println!("  {} {count} directories", "✓".green()),
+        }
+    }
+    for issue in &issues {
+        let marker = match issue.problem {
+            PathProblem::Missing | PathProblem::Duplicate => "!".yellow(),
+            _ => "✗".red(),
+        };
+        println!("  {marker} {}: {}", issue.entry, issue.problem);
+    }
+    problems += issues.len();
+
+    println!("\n{}", "Shell:".bold());
+    // Without `interactive_shell` the shell is never run, so it failing to
+    // start is worth knowing but not a problem
+    let failed = |message: String| {
+        if interactive_shell {
+            println!("  {} {}", "✗".red(), message.red());
+        } else {
+            println!(
+                "  {} {message} {}",
+                "!".yellow(),
+                "(only used with interactive_shell = true)".dimmed()
+            );
+        }
+        usize::from(interactive_shell)
+    };
+    match env::var("SHELL") {
+        Ok(shell) if !shell.is_empty() => {
+            let started = Instant::now();
+            let ctx = Context::new(registry.timeout_for("alias"));
+            match doctor::check_shell(&shell, &ctx) {
+                Ok(()) => println!(
+                    "  {} {shell} starts interactively {}",
+                    "✓".green(),
+                    format!("({:.2}s)", started.elapsed().as_secs_f64()).dimmed()
+                ),
+                Err(err) => problems += failed(format!("{shell}: {err}")),
+            }
+        }
+        _ => problems += failed("$SHELL is not set".to_string()),
+    }
+
+    match problems {
+        0 => println!("\nNo problems found"),
+        1 => println!("\n1 problem found"),
+        _ => println!("\n{problems} problems found"),
+    }
+    if problems > 0 {
+        process::exit(1);
+    }
+}
+
+/// A source's name and status, marked and colored by how it went.
+fn report_line(report: &SourceReport) -> String {
+    let (marker, status) = match &report.status {
+        SourceStatus::Ok(_) => ("✓".green(), report.status.to_string().normal()),
+        SourceStatus::NotInstalled => ("-".dimmed(), report.status.to_string().dimmed()),
+        SourceStatus::Failed { .. } => ("✗".red(), report.status.to_string().red()),
+        SourceStatus::TimedOut => ("!".yellow(), report.status.to_string().yellow()),
+    };
+    format!("{marker} {:<10} {status}", report.name)
+}
+
+/// Load the configuration at `path`, exiting with status 2 if it is invalid.
+fn load_config(path: &Path) -> Config {
+    Config::load(path).unwrap_or_else(|err| {
+        eprintln!("{} {}: {}", "error:".red().bold(), path.display(), err);
+        process::exit(2);
+    })
+}
+
+/// Print the configuration at `path` as TOML, with the defaults of unset
+/// settings filled in.
+fn show_config(path: &Path) {
+    let mut config = load_config(path);
+    config.limit.get_or_insert(DEFAULT_LIMIT);
+    config
+        .timeout
+        .get_or_insert(bento::sources::DEFAULT_TIMEOUT.as_secs_f64());
+    match toml::to_string(&config) {
+        Ok(text) => print!("{text}"),
+        Err(err) => {
+            eprintln!("{} {}", "error:".red().bold(), err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Open the configuration file in the user's editor, creating it from a
+/// commented template first if it does not exist, and check it afterwards.
+fn edit_config(path: &Path) {
+    if !path.exists() {
+        let created = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, config::TEMPLATE));
+        if let Err(err) = created {
+            eprintln!("{} {}: {}", "error:".red().bold(), path.display(), err);
+            process::exit(1);
+        }
+    }
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // Through the shell, so editors given with arguments such as `code -w` work
+    let status = Command::new("sh")
+        .args(["-c", &format!("{editor} \"$1\""), "sh"])
+        .arg(path)
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            load_config(path);
+        }
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(err) => {
+            eprintln!("{} {}: {}", "error:".red().bold(), editor, err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Print `text` to stdout, through `$PAGER` if stdout is a terminal that
+/// it does not fit on.
+fn print_paged(text: &str) {
+    let mut stdout = io::stdout();
+    if stdout.is_terminal()
+        && let Ok((_, height)) = terminal::size()
+        && text.lines().count() >= usize::from(height)
+        && let Some(mut pager) = spawn_pager()
+    {
+        if let Some(mut stdin) = pager.stdin.take() {
+            // The pager may quit before reading everything
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        let _ = pager.wait();
+        return;
+    }
+    if let Err(err) = stdout.write_all(text.as_bytes())
+        && err.kind() != ErrorKind::BrokenPipe
+    {
+        eprintln!("{} {}", "error:".red().bold(), err);
+        process::exit(1);
+    }
+}
+
+/// Start `$PAGER`, or `less`, reading from a pipe.
+fn spawn_pager() -> Option<Child> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    let mut command = Command::new("sh");
+    command.args(["-c", &pager]).stdin(Stdio::piped());
+    // Like git: keep colors, and quit right away if everything fits after all
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    command.spawn().ok()
+}
+
+/// Print every name with more than one definition, in the order the shell
+/// looks them up, marking the one that runs.
+fn print_shadows(
+    out: &mut impl Write,
+    commands: &[BentoCommand],
+    palette: &Palette,
+) -> io::Result<()> {
+    let shadows = resolve::shadows(commands);
+    if shadows.is_empty() {
+        return writeln!(out, "No shadowed commands");
+    }
+    for shadow in &shadows {
+        writeln!(out, "{}", shadow.name.bold())?;
+        for (i, cmd) in shadow.definitions.iter().enumerate() {
+            print_definition(out, cmd, i == 0, &[], palette)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print every definition of `name` in the order the shell looks them up,
+/// with the packages that installed them, then any other packages of that
+/// name. Exits with status 1 if there are none.
+fn print_which(
+    out: &mut impl Write,
+    commands: &[BentoCommand],
+    name: &str,
+    palette: &Palette,
+) -> io::Result<()> {
+    let definitions = resolve::resolve(commands, name);
+    let mut owners = Vec::new();
+    for (i, cmd) in definitions.iter().enumerate() {
+        let owned_by = resolve::owners(commands, cmd);
+        print_definition(out, cmd, i == 0, &owned_by, palette)?;
+        owners.extend(owned_by);
+    }
+
+    let packages: Vec<_> = commands
+        .iter()
+        .filter(|cmd| cmd.name == name && resolve::precedence(&cmd.category).is_none())
+        .filter(|cmd| !owners.contains(cmd))
+        .collect();
+    for cmd in &packages {
+        let category = format!("{:<8}", cmd.category.as_str()).color(palette.color(&cmd.category));
+        let description = cmd.description.as_deref().unwrap_or("package");
+        writeln!(out, "    {category} {}", description.dimmed())?;
+    }
+
+    if definitions.is_empty() && packages.is_empty() {
+        eprintln!("{} {name} not found", "error:".red().bold());
+        process::exit(1);
+    }
+    Ok(())
+}
+
+/// Print one definition of a command: where it lives, or what it expands
+/// to, and which packages installed it. The one the shell runs is marked.
+fn print_definition(
+    out: &mut impl Write,
+    cmd: &BentoCommand,
+    winner: bool,
+    owners: &[&BentoCommand],
+    palette: &Palette,
+) -> io::Result<()> {
+    let marker = if winner {
+        "✓".green().bold()
+    } else {
+        " ".normal()
+    };
+    let category = format!("{:<8}", cmd.category.as_str()).color(palette.color(&cmd.category));
+    let location = match &cmd.path {
+        Some(path) => location(path, cmd.link_target.as_deref()),
+        None => cmd.definition.clone().unwrap_or_default(),
+    };
+    let owners: String = owners
+        .iter()
+        .map(|owner| format!(" ({} {})", owner.category, owner.name))
+        .collect();
+    let location = if winner {
+        location.normal()
+    } else {
+        location.dimmed()
+    };
+    writeln!(out, "  {marker} {category} {location}{}", owners.dimmed())
+}
+
+/// Color `name`, making the characters at `positions` bold and underlined.
+fn highlight(name: &str, positions: &[usize], color: Color) -> String {
+    let mut out = String::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in name.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            out.push_str(&paint(&run, run_matched, color));
+            run.clear();
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    out.push_str(&paint(&run, run_matched, color));
+    out
+}
+
+fn paint(text: &str, matched: bool, color: Color) -> String {
+    let text = text.color(color);
+    if matched {
+        text.bold().underline().to_string()
+    } else {
+        text.to_string()
+    }
+}
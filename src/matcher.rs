@@ -0,0 +1,278 @@
+//! fzf-style fuzzy matching.
+//!
+//! A query matches a target when its characters appear in the target in
+//! order. Among all such alignments the best one is found with dynamic
+//! programming: every matched character earns [`SCORE_MATCH`] plus a bonus
+//! depending on where it lands (start of the name, after a `-`, `_` or `.`,
+//! a camelCase hump, or directly after the previous match), and every run of
+//! skipped characters costs a gap penalty. Exact and prefix matches earn an
+//! extra bonus, and unmatched characters a small length penalty, so shorter
+//! names rank first.
+//!
+//! Descriptions are matched more strictly: every word of the query has to
+//! appear in the description, and any name match ranks above them.
+
+use crate::BentoCommand;
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+
+/// Match at the very start of the target.
+const BONUS_START: i32 = 10;
+/// Match right after a delimiter such as `-`, `_` or `.`.
+const BONUS_BOUNDARY: i32 = 8;
+/// Match on a camelCase hump or the first digit of a number.
+const BONUS_CAMEL: i32 = 7;
+/// Match directly after the previous match. Equal to the cost of the
+/// shortest gap, so runs are preferred over scattered matches.
+const BONUS_CONSECUTIVE: i32 = -(SCORE_GAP_START + SCORE_GAP_EXTENSION);
+/// The first query character's bonus counts this many times.
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// The last match ends a word, e.g. `git` in `git-flow`.
+const BONUS_WORD_END: i32 = 4;
+/// The target starts with the whole query.
+const BONUS_PREFIX: i32 = 8;
+/// The target is the query.
+const BONUS_EXACT: i32 = 32;
+/// Upper bound on the penalty of one point per unmatched target character.
+const MAX_LENGTH_PENALTY: i32 = 32;
+
+/// A successful fuzzy match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// How well the query matched; at least `1`, higher is better.
+    pub score: usize,
+    /// Indices of the matched characters (not bytes) in the target, ascending.
+    pub positions: Vec<usize>,
+}
+
+/// Score how well `query` fuzzy-matches `target`, case-insensitively.
+///
+/// Returns `0` when the query is empty or its characters do not all appear
+/// in the target in order; any match scores at least `1`. Higher is better.
+pub fn fuzzy_match(query: &str, target: &str) -> usize {
+    fuzzy_match_positions(query, target).map_or(0, |m| m.score)
+}
+
+/// Like [`fuzzy_match`], but also return which target characters matched,
+/// e.g. for highlighting them.
+pub fn fuzzy_match_positions(query: &str, target: &str) -> Option<Match> {
+    let query: Vec<char> = query.chars().map(normalize).collect();
+    let original: Vec<char> = target.chars().collect();
+    let target: Vec<char> = original.iter().copied().map(normalize).collect();
+
+    if query.is_empty() || !is_subsequence(&query, &target) {
+        return None;
+    }
+
+    let bonuses: Vec<i32> = (0..original.len())
+        .map(|i| bonus_at(&original, i))
+        .collect();
+    let (mut score, positions) = best_alignment(&query, &target, &bonuses)?;
+
+    if target == query {
+        score += BONUS_EXACT;
+    }
+    if target.starts_with(&query) {
+        score += BONUS_PREFIX;
+    }
+    let unmatched = i32::try_from(target.len() - query.len()).unwrap_or(i32::MAX);
+    score -= unmatched.min(MAX_LENGTH_PENALTY);
+
+    Some(Match {
+        score: usize::try_from(score.max(1)).unwrap_or(1),
+        positions,
+    })
+}
+
+/// Which part of a command a query matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Field {
+    Name,
+    Description,
+}
+
+/// Match `query` against `cmd`'s name, then the names of the packages that
+/// installed it, and, if `describe` is set and neither matches, its
+/// description.
+///
+/// Positions of a package or description match are empty, since they refer
+/// to another text than the name.
+pub fn match_command(query: &str, cmd: &BentoCommand, describe: bool) -> Option<(Field, Match)> {
+    if let Some(m) = fuzzy_match_positions(query, &cmd.name) {
+        return Some((Field::Name, m));
+    }
+    if let Some(score) = cmd
+        .packages
+        .iter()
+        .map(|package| fuzzy_match(query, package))
+        .filter(|&score| score > 0)
+        .max()
+    {
+        return Some((
+            Field::Name,
+            Match {
+                score,
+                positions: Vec::new(),
+            },
+        ));
+    }
+    let description = cmd.description.as_deref().filter(|_| describe)?;
+    let score = match_description(query, description)?;
+    Some((
+        Field::Description,
+        Match {
+            score,
+            positions: Vec::new(),
+        },
+    ))
+}
+
+/// Score a description containing every word of `query`, ignoring case and
+/// diacritics.
+fn match_description(query: &str, description: &str) -> Option<usize> {
+    let haystack: String = description.chars().map(normalize).collect();
+    let mut score = 0;
+    let mut words = 0;
+    for word in query.split_whitespace() {
+        let word: String = word.chars().map(normalize).collect();
+        if !haystack.contains(&word) {
+            return None;
+        }
+        score += fuzzy_match(&word, &haystack);
+        words += 1;
+    }
+    (words > 0).then_some(score.max(1))
+}
+
+/// The best score of any alignment of `query` within `target`, including
+/// the word-end bonus of the last match, and the positions it matched.
+fn best_alignment(query: &[char], target: &[char], bonuses: &[i32]) -> Option<(i32, Vec<usize>)> {
+    let m = target.len();
+    // Score of the best alignment of the query so far ending with a match at
+    // each target position, and the bonus of the run that match belongs to
+    let mut scores: Vec<Option<i32>> = vec![None; m];
+    let mut run_bonuses = vec![0; m];
+    // For every query character after the first, the position of the
+    // previous character's match in the best alignment ending at each position
+    let mut back: Vec<Vec<usize>> = Vec::with_capacity(query.len() - 1);
+
+    for (j, &c) in target.iter().enumerate() {
+        if c == query[0] {
+            scores[j] = Some(SCORE_MATCH + bonuses[j] * BONUS_FIRST_CHAR_MULTIPLIER);
+            run_bonuses[j] = bonuses[j];
+        }
+    }
+
+    for &q in &query[1..] {
+        let mut next: Vec<Option<i32>> = vec![None; m];
+        let mut next_run_bonuses = vec![0; m];
+        let mut previous = vec![0; m];
+        // Best score of a previous match at k <= j - 2, less the gap to j
+        let mut gap: Option<(i32, usize)> = None;
+
+        for j in 1..m {
+            if j >= 2 {
+                let extended = gap.map(|(score, k)| (score + SCORE_GAP_EXTENSION, k));
+                let opened = scores[j - 2].map(|score| (score + SCORE_GAP_START, j - 2));
+                gap = match (extended, opened) {
+                    (Some(e), Some(o)) => Some(if o.0 >= e.0 { o } else { e }),
+                    (e, o) => e.or(o),
+                };
+            }
+            if target[j] != q {
+                continue;
+            }
+
+            let consecutive = scores[j - 1].map(|score| {
+                let bonus = bonuses[j].max(BONUS_CONSECUTIVE).max(run_bonuses[j - 1]);
+                score + SCORE_MATCH + bonus
+            });
+            let after_gap = gap.map(|(score, k)| (score + SCORE_MATCH + bonuses[j], k));
+
+            if let Some(score) = consecutive
+                && after_gap.is_none_or(|(other, _)| score >= other)
+            {
+                next[j] = Some(score);
+                next_run_bonuses[j] = run_bonuses[j - 1].max(bonuses[j]);
+                previous[j] = j - 1;
+            } else if let Some((score, k)) = after_gap {
+                next[j] = Some(score);
+                next_run_bonuses[j] = bonuses[j];
+                previous[j] = k;
+            }
+        }
+
+        scores = next;
+        run_bonuses = next_run_bonuses;
+        back.push(previous);
+    }
+
+    let (score, end) = scores
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| {
+            let ends_word = target.get(j + 1).is_none_or(|&next| is_delimiter(next));
+            score.map(|score| (score + if ends_word { BONUS_WORD_END } else { 0 }, j))
+        })
+        // Prefer the leftmost alignment among equal scores
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+    let mut positions = vec![end];
+    for previous in back.iter().rev() {
+        let last = positions[positions.len() - 1];
+        positions.push(previous[last]);
+    }
+    positions.reverse();
+    Some((score, positions))
+}
+
+/// Bonus for matching the character at `i`, based on the one before it.
+fn bonus_at(chars: &[char], i: usize) -> i32 {
+    let Some(&prev) = i.checked_sub(1).and_then(|p| chars.get(p)) else {
+        return BONUS_START;
+    };
+    let current = chars[i];
+    if is_delimiter(prev) {
+        BONUS_BOUNDARY
+    } else if (prev.is_lowercase() && current.is_uppercase())
+        || (!prev.is_ascii_digit() && current.is_ascii_digit())
+    {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '-' | '_' | '.' | '/' | ' ' | ':' | '@')
+}
+
+fn is_subsequence(query: &[char], target: &[char]) -> bool {
+    let mut target = target.iter();
+    query.iter().all(|q| target.any(|t| t == q))
+}
+
+/// Lowercase `c` and strip diacritics from common Latin letters, so that
+/// `cafe` matches `café`.
+fn normalize(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'ď' => 'd',
+        'è'..='ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ì'..='ï' | 'ī' | 'į' => 'i',
+        'ł' => 'l',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ř' => 'r',
+        'ś' | 'š' => 's',
+        'ť' => 't',
+        'ù'..='ü' | 'ū' | 'ů' | 'ű' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => c,
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::resolve::precedence;
+use crate::{BentoCommand, Location};
+
+/// Collapse commands with the same name into a single entry.
+///
+/// The merged entry takes its category from the definition the shell would
+/// run (an alias, then a function, then the first executable on `PATH`),
+/// falling back to the first package collected, and lists every other
+/// category it was found in under [`BentoCommand::also_in`], and every other
+/// place on `PATH` under [`BentoCommand::also_at`]. Missing details
+/// such as a description are filled in from the other entries. Entries keep
+/// the order in which their names first appeared.
+///
+/// A package installing a single executable of another name, such as the
+/// `ripgrep` crate installing `rg`, is merged into that executable's entry
+/// and listed under [`BentoCommand::packages`].
+pub fn merge(commands: Vec<BentoCommand>) -> Vec<BentoCommand> {
+    let mut groups: Vec<Vec<BentoCommand>> = Vec::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    for cmd in commands {
+        match by_name.get(&cmd.name) {
+            Some(&i) => groups[i].push(cmd),
+            None => {
+                by_name.insert(cmd.name.clone(), groups.len());
+                groups.push(vec![cmd]);
+            }
+        }
+    }
+    for i in 0..groups.len() {
+        let Some(&target) = installed_name(&groups[i]).and_then(|name| by_name.get(name)) else {
+            continue;
+        };
+        if target != i
+            && groups[target]
+                .iter()
+                .any(|cmd| precedence(&cmd.category).is_some())
+        {
+            let packages = std::mem::take(&mut groups[i]);
+            groups[target].extend(packages);
+        }
+    }
+    groups.into_iter().filter_map(merge_group).collect()
+}
+
+/// The executable the packages in `group` install under another name, if
+/// the group is made up of such packages and they install only that one.
+fn installed_name(group: &[BentoCommand]) -> Option<&str> {
+    let first = group.first()?;
+    let [name] = first.provides.as_slice() else {
+        return None;
+    };
+    let packages = group
+        .iter()
+        .all(|cmd| precedence(&cmd.category).is_none() && cmd.provides == first.provides);
+    (packages && *name != first.name).then_some(name.as_str())
+}
+
+fn merge_group(mut group: Vec<BentoCommand>) -> Option<BentoCommand> {
+    // Stable, so executables stay in PATH order and packages in collection order
+    group.sort_by_key(|cmd| precedence(&cmd.category).unwrap_or(u8::MAX));
+    let mut others = group.into_iter();
+    let mut merged = others.next()?;
+
+    for cmd in others {
+        if cmd.name != merged.name && !merged.packages.contains(&cmd.name) {
+            merged.packages.push(cmd.name.clone());
+        }
+        for category in cmd.categories() {
+            if !merged.is_in(category) {
+                merged.also_in.push(category.clone());
+            }
+        }
+        if merged.description.is_none() {
+            merged.description = cmd.description;
+        }
+        if merged.path.is_none() {
+            merged.path = cmd.path;
+            merged.link_target = cmd.link_target;
+        } else if let Some(path) = cmd.path {
+            let link_target = cmd.link_target;
+            add_location(&mut merged, Location { path, link_target });
+        }
+        for location in cmd.also_at {
+            add_location(&mut merged, location);
+        }
+        if merged.definition.is_none() {
+            merged.definition = cmd.definition;
+        }
+        for name in cmd.provides {
+            if !merged.provides.contains(&name) {
+                merged.provides.push(name);
+            }
+        }
+        for name in cmd.packages {
+            if !merged.packages.contains(&name) {
+                merged.packages.push(name);
+            }
+        }
+    }
+    Some(merged)
+}
+
+/// Record another place `merged` was found, unless it is already known.
+fn add_location(merged: &mut BentoCommand, location: Location) {
+    let known = merged.path.as_ref() == Some(&location.path)
+        || merged
+            .also_at
+            .iter()
+            .any(|other| other.path == location.path);
+    if !known {
+        merged.also_at.push(location);
+    }
+}
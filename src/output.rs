@@ -0,0 +1,153 @@
+//! Machine-readable output of search results.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::BentoCommand;
+
+/// A machine-readable output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single JSON array of rows.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// Comma-separated values with a header line.
+    Csv,
+    /// Tab-separated values with a header line.
+    Tsv,
+}
+
+impl Format {
+    /// Every format, in the order they are listed in help text.
+    pub const ALL: [Format; 4] = [Format::Json, Format::Ndjson, Format::Csv, Format::Tsv];
+
+    /// The name used to select this format on the command line.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown output format {s:?}"))
+    }
+}
+
+/// A command together with how well it matched the query.
+#[derive(Debug, Clone, Serialize)]
+pub struct Row<'a> {
+    #[serde(flatten)]
+    pub command: &'a BentoCommand,
+    pub score: usize,
+}
+
+/// Columns of the CSV and TSV formats, matching the keys of a serialized [`Row`].
+pub const COLUMNS: &[&str] = &[
+    "name",
+    "category",
+    "also_in",
+    "description",
+    "definition",
+    "path",
+    "link_target",
+    "also_at",
+    "provides",
+    "packages",
+    "score",
+];
+
+/// Write `rows` to `out` in the given format.
+pub fn write_rows<W: Write>(out: &mut W, format: Format, rows: &[Row<'_>]) -> io::Result<()> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *out, rows)?;
+            writeln!(out)
+        }
+        Format::Ndjson => {
+            for row in rows {
+                serde_json::to_writer(&mut *out, row)?;
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        Format::Csv => write_delimited(out, rows, ',', csv_escape),
+        Format::Tsv => write_delimited(out, rows, '\t', tsv_escape),
+    }
+}
+
+fn write_delimited<W: Write>(
+    out: &mut W,
+    rows: &[Row<'_>],
+    separator: char,
+    escape: fn(&str) -> String,
+) -> io::Result<()> {
+    let separator = separator.to_string();
+    writeln!(out, "{}", COLUMNS.join(&separator))?;
+    for row in rows {
+        let value = serde_json::to_value(row)?;
+        let fields: Vec<String> = COLUMNS
+            .iter()
+            .map(|column| escape(&field(value.get(column))))
+            .collect();
+        writeln!(out, "{}", fields.join(&separator))?;
+    }
+    Ok(())
+}
+
+/// Flatten a JSON value into a single field; lists are joined with `;`, and
+/// a [`Location`](crate::Location) is written as its path and link target.
+fn field(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Object(location)) => match location.get("link_target") {
+            Some(target) => format!("{} → {}", field(location.get("path")), field(Some(target))),
+            None => field(location.get("path")),
+        },
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| field(Some(item)))
+            .collect::<Vec<_>>()
+            .join(";"),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape characters that would break TSV's one-record-per-line layout.
+fn tsv_escape(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
@@ -0,0 +1,474 @@
+//! Full-screen interactive picker that filters commands as you type.
+//!
+//! The picker draws on the terminal (`/dev/tty`) rather than stdout, so the
+//! chosen command can be printed to stdout and captured by a shell pipeline.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::{
+    BentoCommand, Category, CategoryFilter, Palette, SearchMode, SearchQuery, SearchResult,
+    Searcher,
+};
+
+/// Narrowest terminal, in columns, that gets a preview pane next to the list.
+const MIN_PREVIEW_WIDTH: usize = 80;
+
+/// What the picker should do after handling a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Keep reading keys.
+    Continue,
+    /// Return the selected command.
+    Accept,
+    /// Quit without selecting anything.
+    Abort,
+}
+
+/// Query, category filter and selection state of the picker.
+///
+/// Kept separate from the terminal handling so it can be driven by tests.
+pub struct Picker<'a> {
+    commands: &'a [BentoCommand],
+    query: String,
+    categories: Vec<(Category, bool)>,
+    results: Vec<SearchResult<'a>>,
+    selected: usize,
+    describe: bool,
+    palette: Palette,
+}
+
+impl<'a> Picker<'a> {
+    /// Create a picker over `commands`, starting with `query` typed in and
+    /// every category enabled.
+    pub fn new(commands: &'a [BentoCommand], query: &str) -> Self {
+        let mut categories: Vec<Category> = Vec::new();
+        for category in commands.iter().flat_map(BentoCommand::categories) {
+            if !categories.contains(category) {
+                categories.push(category.clone());
+            }
+        }
+        categories.sort();
+
+        let mut picker = Self {
+            commands,
+            query: query.to_string(),
+            categories: categories.into_iter().map(|c| (c, true)).collect(),
+            results: Vec::new(),
+            selected: 0,
+            describe: false,
+            palette: Palette::new(),
+        };
+        picker.refresh();
+        picker
+    }
+
+    /// The text typed so far.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Categories present in the command list, and whether each is enabled.
+    pub fn categories(&self) -> &[(Category, bool)] {
+        &self.categories
+    }
+
+    /// Matching commands, best first, with the positions that matched.
+    pub fn results(&self) -> &[SearchResult<'a>] {
+        &self.results
+    }
+
+    /// Index of the highlighted row in [`Picker::results`].
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// The highlighted command, if there are any results.
+    pub fn selected(&self) -> Option<&'a BentoCommand> {
+        self.results.get(self.selected).map(|result| result.command)
+    }
+
+    /// Replace the query.
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.refresh();
+    }
+
+    /// Also match descriptions, ranking those matches below name matches.
+    pub fn set_describe(&mut self, describe: bool) {
+        self.describe = describe;
+        self.refresh();
+    }
+
+    /// The colors categories are drawn in.
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Draw categories in the colors of `palette`.
+    pub fn set_palette(&mut self, palette: &Palette) {
+        self.palette = palette.clone();
+    }
+
+    /// Enable or disable the category at `index` in [`Picker::categories`].
+    pub fn toggle_category(&mut self, index: usize) {
+        if let Some((_, enabled)) = self.categories.get_mut(index) {
+            *enabled = !*enabled;
+            self.refresh();
+        }
+    }
+
+    /// Enable only `category`, or every category if it is `None`.
+    pub fn solo(&mut self, category: Option<&Category>) {
+        for (c, enabled) in &mut self.categories {
+            *enabled = category.is_none_or(|category| c == category);
+        }
+        self.refresh();
+    }
+
+    /// Enable exactly the categories `filter` allows.
+    pub fn set_filter(&mut self, filter: &CategoryFilter) {
+        for (category, enabled) in &mut self.categories {
+            *enabled = filter.allows(category);
+        }
+        self.refresh();
+    }
+
+    /// Cycle through showing a single category at a time, then all of them.
+    pub fn cycle_category(&mut self, forward: bool) {
+        let count = self.categories.len();
+        let enabled: Vec<usize> = (0..count).filter(|&i| self.categories[i].1).collect();
+        // Position in the cycle: 0 is "all", i + 1 is the i-th category alone
+        let current = match enabled.as_slice() {
+            [only] if count > 1 => only + 1,
+            _ => 0,
+        };
+        let next = if forward {
+            (current + 1) % (count + 1)
+        } else {
+            (current + count) % (count + 1)
+        };
+        let category = next.checked_sub(1).map(|i| self.categories[i].0.clone());
+        self.solo(category.as_ref());
+    }
+
+    /// Move the selection by `delta` rows, staying within the results.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.results.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Update the state for a key press.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Enter => return Action::Accept,
+            KeyCode::Esc => return Action::Abort,
+            KeyCode::Char('c' | 'd' | 'g') if ctrl => return Action::Abort,
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Down => self.move_selection(1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_selection(-1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-10),
+            KeyCode::PageDown => self.move_selection(10),
+            KeyCode::Tab => self.cycle_category(true),
+            KeyCode::BackTab => self.cycle_category(false),
+            KeyCode::Char('u') if ctrl => self.set_query(""),
+            KeyCode::Char(c @ '0'..='9') if alt => {
+                // Alt-1 is the first category, Alt-0 the tenth
+                let digit = c.to_digit(10).unwrap_or_default() as usize;
+                self.toggle_category((digit + 9) % 10);
+            }
+            KeyCode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(&query);
+            }
+            KeyCode::Char(c) if !ctrl && !alt => {
+                let query = format!("{}{c}", self.query);
+                self.set_query(&query);
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn refresh(&mut self) {
+        let enabled: Vec<Category> = self
+            .categories
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(category, _)| category.clone())
+            .collect();
+        // An empty include list would allow every category
+        self.results = if enabled.is_empty() {
+            Vec::new()
+        } else {
+            let mode = if self.describe {
+                SearchMode::Descriptions
+            } else {
+                SearchMode::Names
+            };
+            let query = SearchQuery::new(self.query.as_str())
+                .with_categories(enabled)
+                .with_mode(mode);
+            Searcher::new(self.commands).search(&query).results
+        };
+        self.selected = 0;
+    }
+}
+
+/// Run the picker on the terminal and return the chosen command, or `None`
+/// if the user quit without choosing.
+pub fn pick(
+    commands: &[BentoCommand],
+    query: &str,
+    filter: &CategoryFilter,
+    describe: bool,
+    palette: &Palette,
+) -> io::Result<Option<BentoCommand>> {
+    let mut picker = Picker::new(commands, query);
+    picker.set_filter(filter);
+    picker.set_palette(palette);
+    if describe {
+        picker.set_describe(true);
+    }
+
+    let mut tty: Box<dyn Write> = match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(tty) => Box::new(tty),
+        Err(_) => Box::new(io::stderr()),
+    };
+    terminal::enable_raw_mode()?;
+    // Restore the terminal however far setting it up got
+    let result = execute!(tty, terminal::EnterAlternateScreen, cursor::Hide)
+        .and_then(|()| run(&mut picker, &mut tty));
+
+    let _ = execute!(tty, cursor::Show, terminal::LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+
+    Ok(result?.then(|| picker.selected().cloned()).flatten())
+}
+
+/// Draw and handle keys until the user accepts (`true`) or aborts (`false`).
+fn run<W: Write>(picker: &mut Picker<'_>, tty: &mut W) -> io::Result<bool> {
+    let mut offset = 0;
+    loop {
+        let (width, height) = terminal::size()?;
+        let rows = usize::from(height).saturating_sub(3).max(1);
+        if picker.selected_index() < offset {
+            offset = picker.selected_index();
+        } else if picker.selected_index() >= offset + rows {
+            offset = picker.selected_index() + 1 - rows;
+        }
+        draw(picker, tty, usize::from(width), rows, offset)?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind != KeyEventKind::Release
+        {
+            match picker.handle_key(key) {
+                Action::Continue => {}
+                Action::Accept => return Ok(true),
+                Action::Abort => return Ok(false),
+            }
+        }
+    }
+}
+
+fn draw<W: Write>(
+    picker: &Picker<'_>,
+    tty: &mut W,
+    width: usize,
+    rows: usize,
+    offset: usize,
+) -> io::Result<()> {
+    queue!(
+        tty,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+
+    // Prompt
+    queue!(
+        tty,
+        SetForegroundColor(Color::Cyan),
+        Print("> "),
+        SetForegroundColor(Color::Reset),
+        Print(picker.query()),
+        SetAttribute(Attribute::Reverse),
+        Print(" "),
+        SetAttribute(Attribute::Reset),
+    )?;
+
+    // Category toggles, numbered for Alt-<n>
+    queue!(tty, cursor::MoveTo(0, 1))?;
+    for (i, (category, enabled)) in picker.categories().iter().enumerate() {
+        let label = if i < 10 {
+            format!("{}:{} ", (i + 1) % 10, category)
+        } else {
+            format!("{category} ")
+        };
+        if *enabled {
+            queue!(
+                tty,
+                SetForegroundColor(terminal_color(picker.palette().color(category))),
+                Print(label)
+            )?;
+        } else {
+            queue!(
+                tty,
+                SetForegroundColor(Color::DarkGrey),
+                SetAttribute(Attribute::CrossedOut),
+                Print(label),
+                SetAttribute(Attribute::Reset)
+            )?;
+        }
+    }
+    queue!(
+        tty,
+        SetForegroundColor(Color::DarkGrey),
+        cursor::MoveTo(0, 2),
+        Print(format!(
+            "  {}/{}  (tab: next category, alt-<n>: toggle, enter: select, esc: quit)",
+            picker.results().len(),
+            picker.commands.len()
+        )),
+        SetForegroundColor(Color::Reset),
+    )?;
+
+    // Show the selected command's definition or details alongside the list
+    // when there is room for both
+    let preview = picker.selected().map(preview).unwrap_or_default();
+    let list_width = if width >= MIN_PREVIEW_WIDTH && !preview.is_empty() {
+        width / 2
+    } else {
+        width
+    };
+
+    for (row, result) in picker.results().iter().skip(offset).take(rows).enumerate() {
+        let cmd = result.command;
+        let index = offset + row;
+        let selected = index == picker.selected_index();
+        queue!(tty, cursor::MoveTo(0, (row + 3) as u16))?;
+        if selected {
+            queue!(tty, SetAttribute(Attribute::Reverse), Print("> "))?;
+        } else {
+            queue!(tty, Print("  "))?;
+        }
+
+        let color = terminal_color(picker.palette().color(&cmd.category));
+        let budget = list_width.saturating_sub(cmd.category.as_str().len() + 5);
+        for (i, c) in cmd.name.chars().take(budget).enumerate() {
+            if result.matched.positions.contains(&i) {
+                queue!(
+                    tty,
+                    SetForegroundColor(color),
+                    SetAttribute(Attribute::Bold),
+                    SetAttribute(Attribute::Underlined),
+                    Print(c),
+                    SetAttribute(Attribute::NoUnderline),
+                    SetAttribute(Attribute::NormalIntensity),
+                )?;
+            } else {
+                queue!(tty, SetForegroundColor(color), Print(c))?;
+            }
+        }
+        let used = 2 + cmd.name.chars().count().min(budget);
+        let mut rest = String::new();
+        if let Some(expansion) = cmd.expansion() {
+            rest.push_str(&format!(" → {expansion}"));
+        }
+        let categories: Vec<&str> = cmd.categories().map(Category::as_str).collect();
+        rest.push_str(&format!(" ({})", categories.join(", ")));
+        if let Some(description) = &cmd.description {
+            rest.push_str(&format!(" - {description}"));
+        }
+        let rest: String = rest.chars().take(list_width.saturating_sub(used)).collect();
+        queue!(
+            tty,
+            SetForegroundColor(Color::DarkGrey),
+            Print(rest),
+            SetAttribute(Attribute::Reset),
+            SetForegroundColor(Color::Reset),
+        )?;
+    }
+
+    if list_width < width {
+        let preview_width = width - list_width - 2;
+        for row in 0..rows {
+            let line = preview.get(row).map_or("", String::as_str);
+            queue!(
+                tty,
+                cursor::MoveTo(list_width as u16, (row + 3) as u16),
+                terminal::Clear(terminal::ClearType::UntilNewLine),
+                SetForegroundColor(Color::DarkGrey),
+                Print("│ "),
+                SetForegroundColor(Color::Reset),
+                Print(line.chars().take(preview_width).collect::<String>()),
+            )?;
+        }
+    }
+
+    tty.flush()
+}
+
+/// Lines shown in the preview pane for `cmd`: its source code if it is a
+/// function or alias, otherwise what else is known about it.
+fn preview(cmd: &BentoCommand) -> Vec<String> {
+    if let Some(definition) = &cmd.definition {
+        return definition
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect();
+    }
+    let mut lines = Vec::new();
+    if let Some(description) = &cmd.description {
+        lines.push(description.clone());
+    }
+    if let Some(path) = &cmd.path {
+        lines.push(path.display().to_string());
+    }
+    if let Some(target) = &cmd.link_target {
+        lines.push(format!("→ {}", target.display()));
+    }
+    for other in &cmd.also_at {
+        lines.push(format!("also {}", other.path.display()));
+    }
+    if !cmd.packages.is_empty() {
+        lines.push(format!("installed by: {}", cmd.packages.join(", ")));
+    }
+    if !cmd.provides.is_empty() {
+        lines.push(format!("provides: {}", cmd.provides.join(", ")));
+    }
+    lines
+}
+
+/// `color` in crossterm's palette, where the plain names are the bright
+/// variants.
+fn terminal_color(color: colored::Color) -> Color {
+    use colored::Color as C;
+    match color {
+        C::Black => Color::Black,
+        C::Red => Color::DarkRed,
+        C::Green => Color::DarkGreen,
+        C::Yellow => Color::DarkYellow,
+        C::Blue => Color::DarkBlue,
+        C::Magenta => Color::DarkMagenta,
+        C::Cyan => Color::DarkCyan,
+        C::White => Color::Grey,
+        C::BrightBlack => Color::DarkGrey,
+        C::BrightRed => Color::Red,
+        C::BrightGreen => Color::Green,
+        C::BrightYellow => Color::Yellow,
+        C::BrightBlue => Color::Blue,
+        C::BrightMagenta => Color::Magenta,
+        C::BrightCyan => Color::Cyan,
+        C::BrightWhite => Color::White,
+        C::TrueColor { r, g, b } => Color::Rgb { r, g, b },
+        C::AnsiColor(n) => Color::AnsiValue(n),
+    }
+}
@@ -0,0 +1,142 @@
+//! How the shell resolves a command name.
+//!
+//! Aliases are expanded first, then functions are looked up, and only then
+//! is `PATH` searched, directory by directory. When a name is defined more
+//! than once, the first definition in that order wins and the others are
+//! shadowed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path};
+
+use crate::{BentoCommand, Category};
+
+/// A name with more than one definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shadow<'a> {
+    pub name: &'a str,
+    /// Every definition in resolution order; the first one is the one the
+    /// shell runs.
+    pub definitions: Vec<&'a BentoCommand>,
+}
+
+impl<'a> Shadow<'a> {
+    /// The definition the shell runs.
+    pub fn winner(&self) -> &'a BentoCommand {
+        self.definitions[0]
+    }
+}
+
+/// Where commands of `category` come in the shell's lookup, or `None` if the
+/// shell does not run them by name, as with packages.
+pub fn precedence(category: &Category) -> Option<u8> {
+    match category {
+        Category::Alias => Some(0),
+        Category::Function => Some(1),
+        Category::Bin => Some(2),
+        _ => None,
+    }
+}
+
+/// Every definition of `name` the shell could run, in resolution order.
+///
+/// `commands` must list `PATH` executables in `PATH` order, as collected.
+pub fn resolve<'a>(commands: &'a [BentoCommand], name: &str) -> Vec<&'a BentoCommand> {
+    order(commands.iter().filter(|cmd| cmd.name == name).collect())
+}
+
+/// The packages among `commands` that installed `cmd`, an executable on
+/// `PATH`.
+///
+/// Homebrew and npm are recognized by where the executable links to, and
+/// Cargo and pip packages by the executables they report installing, when
+/// `cmd` is in a Cargo or Python `bin` directory.
+pub fn owners<'a>(commands: &'a [BentoCommand], cmd: &BentoCommand) -> Vec<&'a BentoCommand> {
+    let Some(path) = &cmd.path else {
+        return Vec::new();
+    };
+    let file = cmd
+        .link_target
+        .clone()
+        .or_else(|| fs::canonicalize(path).ok())
+        .unwrap_or_else(|| path.clone());
+    let dir = path.parent();
+
+    commands
+        .iter()
+        .filter(|package| match package.category {
+            Category::Homebrew => after(&file, "Cellar").as_deref() == Some(&package.name),
+            Category::Cask => after(&file, "Caskroom").as_deref() == Some(&package.name),
+            Category::Npm | Category::Yarn => {
+                after(&file, "node_modules").as_deref() == Some(&package.name)
+            }
+            Category::Cargo => {
+                package.provides.contains(&cmd.name) && dir.is_some_and(is_cargo_bin)
+            }
+            Category::Pip => package.provides.contains(&cmd.name) && dir.is_some_and(is_python_bin),
+            _ => false,
+        })
+        .collect()
+}
+
+/// The path component following `marker`, joined with the next one for
+/// scoped npm packages such as `node_modules/@scope/name`.
+fn after(path: &Path, marker: &str) -> Option<String> {
+    let mut components = path.components().map(Component::as_os_str);
+    components.find(|component| *component == marker)?;
+    let name = components.next()?.to_str()?;
+    if name.starts_with('@') {
+        Some(format!("{name}/{}", components.next()?.to_str()?))
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// Whether `dir` is the `bin` directory of a Cargo install root.
+fn is_cargo_bin(dir: &Path) -> bool {
+    dir.file_name().is_some_and(|name| name == "bin")
+        && dir.parent().is_some_and(|root| {
+            root.join(".crates.toml").exists() || root.join(".crates2.json").exists()
+        })
+}
+
+/// Whether `dir` is the `bin` directory of a Python installation or virtualenv.
+fn is_python_bin(dir: &Path) -> bool {
+    dir.join("python").exists() || dir.join("python3").exists()
+}
+
+/// Every name with more than one definition, sorted by name.
+pub fn shadows(commands: &[BentoCommand]) -> Vec<Shadow<'_>> {
+    let mut by_name: BTreeMap<&str, Vec<&BentoCommand>> = BTreeMap::new();
+    for cmd in commands {
+        by_name.entry(&cmd.name).or_default().push(cmd);
+    }
+    by_name
+        .into_iter()
+        .map(|(name, definitions)| Shadow {
+            name,
+            definitions: order(definitions),
+        })
+        .filter(|shadow| shadow.definitions.len() > 1)
+        .collect()
+}
+
+/// Keep the definitions the shell can run, in resolution order, dropping
+/// repeats of the same file, e.g. from `/bin` when it links to `/usr/bin`.
+fn order(mut definitions: Vec<&BentoCommand>) -> Vec<&BentoCommand> {
+    definitions.retain(|cmd| precedence(&cmd.category).is_some());
+    // Stable, so executables stay in PATH order
+    definitions.sort_by_key(|cmd| precedence(&cmd.category));
+    let mut seen = Vec::new();
+    definitions.retain(|cmd| {
+        let file = cmd
+            .path
+            .as_ref()
+            .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()));
+        let key = (&cmd.category, file);
+        let first = !seen.contains(&key);
+        seen.push(key);
+        first
+    });
+    definitions
+}
@@ -0,0 +1,156 @@
+//! Searching a list of commands: filtering by category, matching, ranking
+//! and truncating, shared by the command line and the picker.
+
+use crate::{BentoCommand, Category, CategoryFilter, Field, Match, match_command};
+
+/// What a query is matched against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Command names only.
+    #[default]
+    Names,
+    /// Command names, then descriptions, ranking description matches below
+    /// every name match.
+    Descriptions,
+}
+
+/// A search: the text to match, which categories to search and how many
+/// results to return.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Text to match; empty matches every command.
+    pub text: String,
+    pub filter: CategoryFilter,
+    /// Most results to return, or `None` for all of them.
+    pub limit: Option<usize>,
+    pub mode: SearchMode,
+}
+
+impl SearchQuery {
+    /// A query for `text` in every category, without a limit.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Search only the categories `filter` allows.
+    pub fn with_filter(mut self, filter: CategoryFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Search only `categories`.
+    pub fn with_categories(mut self, categories: Vec<Category>) -> Self {
+        self.filter.include = categories;
+        self
+    }
+
+    /// Leave out `categories`.
+    pub fn with_excludes(mut self, categories: Vec<Category>) -> Self {
+        self.filter.exclude = categories;
+        self
+    }
+
+    /// Return at most `limit` results, or all of them if it is `None`.
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Match against what `mode` says.
+    pub fn with_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// A command that matched a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<'a> {
+    pub command: &'a BentoCommand,
+    /// Which part of the command matched.
+    pub field: Field,
+    /// How well it matched, and the matching characters of its name.
+    pub matched: Match,
+}
+
+/// The ranked results of a search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchResults<'a> {
+    /// The best results, up to the query's limit.
+    pub results: Vec<SearchResult<'a>>,
+    /// How many commands matched, including those past the limit.
+    pub total: usize,
+}
+
+impl SearchResults<'_> {
+    /// How many matches were left out by the limit.
+    pub fn omitted(&self) -> usize {
+        self.total - self.results.len()
+    }
+}
+
+/// Searches a list of commands.
+#[derive(Debug, Clone, Copy)]
+pub struct Searcher<'a> {
+    commands: &'a [BentoCommand],
+}
+
+impl<'a> Searcher<'a> {
+    /// Create a searcher over `commands`.
+    pub fn new(commands: &'a [BentoCommand]) -> Self {
+        Self { commands }
+    }
+
+    /// The commands matching `query`, best first.
+    ///
+    /// Name matches come first, then description matches; each is ordered
+    /// by score, then shorter names, then alphabetically. An empty query
+    /// matches every command the filter allows, in the order given.
+    pub fn search(&self, query: &SearchQuery) -> SearchResults<'a> {
+        let candidates = self.commands.iter().filter(|cmd| query.filter.matches(cmd));
+        let mut results: Vec<SearchResult<'a>> = if query.text.is_empty() {
+            candidates
+                .map(|command| SearchResult {
+                    command,
+                    field: Field::Name,
+                    matched: Match {
+                        score: 0,
+                        positions: Vec::new(),
+                    },
+                })
+                .collect()
+        } else {
+            let describe = query.mode == SearchMode::Descriptions;
+            let mut results: Vec<_> = candidates
+                .filter_map(|command| {
+                    let (field, matched) = match_command(&query.text, command, describe)?;
+                    Some(SearchResult {
+                        command,
+                        field,
+                        matched,
+                    })
+                })
+                .collect();
+            results.sort_by(|a, b| {
+                a.field
+                    .cmp(&b.field)
+                    .then_with(|| b.matched.score.cmp(&a.matched.score))
+                    .then_with(|| {
+                        let len = |result: &SearchResult| result.command.name.chars().count();
+                        len(a).cmp(&len(b))
+                    })
+                    .then_with(|| a.command.name.cmp(&b.command.name))
+            });
+            results
+        };
+
+        let total = results.len();
+        if let Some(limit) = query.limit {
+            results.truncate(limit);
+        }
+        SearchResults { results, total }
+    }
+}
@@ -0,0 +1,67 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{Context, Source, first_line, home_dir};
+use crate::{BentoCommand, Category};
+
+/// Crates installed with `cargo install`.
+pub struct CargoSource;
+
+impl Source for CargoSource {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn category(&self) -> Category {
+        Category::Cargo
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("cargo")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "cargo", &["--version"])
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        ctx.stdout("cargo", &["install", "--list"])
+            .map(|stdout| parse(&stdout, self.category()))
+            .unwrap_or_default()
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        let Some(cargo_home) = cargo_home() else {
+            return Vec::new();
+        };
+        vec![
+            cargo_home.join(".crates.toml"),
+            cargo_home.join(".crates2.json"),
+        ]
+    }
+}
+
+/// `$CARGO_HOME`, defaulting to `~/.cargo`.
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".cargo")))
+}
+
+/// Parse `cargo install --list`: unindented `name vX.Y.Z:` package lines,
+/// each followed by the binaries it installed, indented.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    let mut commands: Vec<BentoCommand> = Vec::new();
+    for line in stdout.lines() {
+        if line.starts_with(' ') {
+            if let Some(package) = commands.last_mut() {
+                package.provides.push(line.trim().to_string());
+            }
+        } else if line.contains(" v")
+            && let Some(name) = line.split(" v").next()
+        {
+            commands.push(BentoCommand::new(name.to_string(), category.clone()));
+        }
+    }
+    commands
+}
@@ -0,0 +1,137 @@
+use std::io;
+use std::process::Output;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{CommandRunner, SystemRunner};
+
+/// How far off a deadline is put when its timeout is too large to add to an
+/// [`Instant`], which in practice means never.
+const FOREVER: Duration = Duration::from_secs(60 * 60 * 24 * 365 * 30);
+
+/// Per-collection state handed to [`Source::collect`](super::Source::collect).
+///
+/// Sources should spawn processes through [`Context::output`] so that they
+/// are killed once the source's timeout has elapsed.
+pub struct Context {
+    deadline: Instant,
+    timed_out: AtomicBool,
+    failure: Mutex<Option<(Option<i32>, String)>>,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Context {
+    /// Create a context whose deadline is `timeout` from now.
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_runner(timeout, Arc::new(SystemRunner))
+    }
+
+    /// Create a context whose deadline is `timeout` from now and which runs
+    /// commands with `runner`.
+    pub fn with_runner(timeout: Duration, runner: Arc<dyn CommandRunner>) -> Self {
+        Self {
+            deadline: deadline_after(Instant::now(), timeout),
+            timed_out: AtomicBool::new(false),
+            failure: Mutex::new(None),
+            runner,
+        }
+    }
+
+    /// A context for optional extra work, such as looking up descriptions,
+    /// that gives up after at most `limit`.
+    ///
+    /// Its deadline is never later than this one's, and neither running out
+    /// of time nor failing marks this context as timed out or failed.
+    pub fn limited(&self, limit: Duration) -> Self {
+        Self::with_runner(
+            self.remaining().unwrap_or_default().min(limit),
+            Arc::clone(&self.runner),
+        )
+    }
+
+    /// Time left before the deadline, or `None` once it has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    /// Whether the deadline was hit while running a command.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    /// Record that the source failed, with the exit code of the command that
+    /// failed, if there was one, and what went wrong. Only the first failure
+    /// is kept.
+    pub fn report_failure(&self, code: Option<i32>, message: impl Into<String>) {
+        if let Ok(mut failure) = self.failure.lock()
+            && failure.is_none()
+        {
+            *failure = Some((code, message.into()));
+        }
+    }
+
+    /// The first failure reported, as an exit code and message.
+    pub fn failure(&self) -> Option<(Option<i32>, String)> {
+        self.failure.lock().ok()?.clone()
+    }
+
+    /// Whether `program` is installed, as far as the runner commands are
+    /// spawned with knows.
+    pub fn exists(&self, program: &str) -> bool {
+        self.runner.exists(program)
+    }
+
+    /// Run `program` with `args` and capture its output.
+    ///
+    /// The process is killed, and an error of kind [`io::ErrorKind::TimedOut`]
+    /// returned, if it is still running when the deadline passes.
+    pub fn output(&self, program: &str, args: &[&str]) -> io::Result<Output> {
+        if self.remaining().is_none() {
+            return Err(self.expire());
+        }
+        match self.runner.run(program, args, self.deadline) {
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => Err(self.expire()),
+            result => result,
+        }
+    }
+
+    /// Run `program` with `args` and return its stdout, or `None` if it could
+    /// not be spawned or timed out.
+    ///
+    /// A program that cannot be spawned or exits unsuccessfully is reported
+    /// with [`Context::report_failure`]; its stdout is still returned, since
+    /// some tools exit with an error after listing what they could.
+    pub fn stdout(&self, program: &str, args: &[&str]) -> Option<String> {
+        match self.output(program, args) {
+            Ok(output) => {
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    self.report_failure(output.status.code(), stderr.trim());
+                }
+                Some(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+            Err(err) => {
+                if err.kind() != io::ErrorKind::TimedOut {
+                    self.report_failure(None, format!("{program}: {err}"));
+                }
+                None
+            }
+        }
+    }
+
+    fn expire(&self) -> io::Error {
+        self.timed_out.store(true, Ordering::Relaxed);
+        io::Error::new(io::ErrorKind::TimedOut, "source timed out")
+    }
+}
+
+/// The instant `timeout` after `start`, or far in the future if that does
+/// not fit in an [`Instant`].
+pub(crate) fn deadline_after(start: Instant, timeout: Duration) -> Instant {
+    start
+        .checked_add(timeout)
+        .unwrap_or_else(|| start + FOREVER)
+}
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use super::path::{absolute, executables};
+use super::{Context, Source};
+use crate::{BentoCommand, Category};
+
+/// Commands from the user's own tooling: the executables in a directory, or
+/// the lines printed by a shell command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomSource {
+    name: String,
+    category: Category,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    Dir(PathBuf),
+    Command(String),
+}
+
+impl CustomSource {
+    /// A source listing the executables in `dir`. Relative paths are taken
+    /// from the current directory.
+    pub fn dir(name: impl Into<String>, category: Category, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            category,
+            kind: Kind::Dir(dir.into()),
+        }
+    }
+
+    /// A source running `command` with `sh -c` and listing one command per
+    /// line of its output, either `name` or `name<TAB>description`.
+    pub fn command(
+        name: impl Into<String>,
+        category: Category,
+        command: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            category,
+            kind: Kind::Command(command.into()),
+        }
+    }
+}
+
+impl Source for CustomSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn category(&self) -> Category {
+        self.category.clone()
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        match &self.kind {
+            Kind::Dir(dir) => executables(&absolute(dir), self.category()).unwrap_or_else(|err| {
+                ctx.report_failure(None, format!("{}: {err}", dir.display()));
+                Vec::new()
+            }),
+            Kind::Command(command) => ctx
+                .stdout("sh", &["-c", command])
+                .map(|stdout| parse(&stdout, self.category()))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        // A command's output can change at any time, so it relies on expiry
+        match &self.kind {
+            Kind::Dir(dir) => vec![absolute(Path::new(dir))],
+            Kind::Command(_) => Vec::new(),
+        }
+    }
+
+    fn settings(&self) -> String {
+        match &self.kind {
+            Kind::Dir(dir) => format!("{} dir {}", self.category, dir.display()),
+            Kind::Command(command) => format!("{} command {command}", self.category),
+        }
+    }
+}
+
+/// Parse `name` or `name<TAB>description` lines, skipping blank ones.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, description) = match line.split_once('\t') {
+                Some((name, description)) => (name.trim(), description.trim()),
+                None => (line.trim(), ""),
+            };
+            if name.is_empty() {
+                return None;
+            }
+            let cmd = BentoCommand::new(name, category.clone());
+            Some(if description.is_empty() {
+                cmd
+            } else {
+                cmd.with_description(description)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+use std::env;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::{Context, Source, first_line};
+use crate::{BentoCommand, Category};
+
+/// Go modules reported by `go list -m all`.
+pub struct GoSource;
+
+impl Source for GoSource {
+    fn name(&self) -> &str {
+        "go"
+    }
+
+    fn category(&self) -> Category {
+        Category::Go
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("go")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "go", &["version"])
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let output = match ctx.output("go", &["list", "-m", "all"]) {
+            Ok(output) => output,
+            Err(err) => {
+                if err.kind() != ErrorKind::TimedOut {
+                    ctx.report_failure(None, format!("go: {err}"));
+                }
+                return Vec::new();
+            }
+        };
+        if !output.status.success() {
+            // Outside a module there is simply nothing to list
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !stderr.contains("go.mod file not found") {
+                ctx.report_failure(output.status.code(), stderr.trim());
+            }
+        }
+        parse(&String::from_utf8_lossy(&output.stdout), self.category())
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        // `go list -m all` lists the modules of the current directory's module
+        env::current_dir()
+            .map(|dir| vec![dir.join("go.mod"), dir.join("go.sum")])
+            .unwrap_or_default()
+    }
+}
+
+/// Parse `module/path version` lines, keeping the last path segment as the
+/// name. The main module comes first, without a version, and is skipped.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let module = fields.next()?;
+            fields.next()?;
+            Some(module)
+        })
+        .filter(|module| module.contains('/'))
+        .filter_map(|module| module.rsplit('/').next())
+        .map(|name| BentoCommand::new(name.to_string(), category.clone()))
+        .collect()
+}
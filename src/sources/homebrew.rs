@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use super::{Context, DESCRIPTION_TIMEOUT, Source, first_line};
+use crate::{BentoCommand, Category};
+
+/// Installed Homebrew formulae.
+pub struct HomebrewSource;
+
+impl Source for HomebrewSource {
+    fn name(&self) -> &str {
+        "homebrew"
+    }
+
+    fn category(&self) -> Category {
+        Category::Homebrew
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("brew")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "brew", &["--version"])
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands = ctx
+            .stdout("brew", &["list", "--formula"])
+            .map(|stdout| parse_list(&stdout, self.category()))
+            .unwrap_or_default();
+        describe(&mut commands, ctx, "formulae", "name");
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        prefixes().map(|prefix| prefix.join("Cellar")).collect()
+    }
+}
+
+/// Installed Homebrew casks.
+pub struct CaskSource;
+
+impl Source for CaskSource {
+    fn name(&self) -> &str {
+        "cask"
+    }
+
+    fn category(&self) -> Category {
+        Category::Cask
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("brew")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "brew", &["--version"])
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands = ctx
+            .stdout("brew", &["list", "--cask"])
+            .map(|stdout| parse_list(&stdout, self.category()))
+            .unwrap_or_default();
+        describe(&mut commands, ctx, "casks", "token");
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        prefixes().map(|prefix| prefix.join("Caskroom")).collect()
+    }
+}
+
+/// Directories Homebrew may be installed into, most specific first.
+fn prefixes() -> impl Iterator<Item = PathBuf> {
+    env::var_os("HOMEBREW_PREFIX")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(
+            ["/opt/homebrew", "/usr/local", "/home/linuxbrew/.linuxbrew"]
+                .into_iter()
+                .map(PathBuf::from),
+        )
+}
+
+/// Parse the one-name-per-line output of `brew list`.
+fn parse_list(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| BentoCommand::new(name.to_string(), category.clone()))
+        .collect()
+}
+
+/// Fill in descriptions from `brew info`, whose JSON lists formulae and casks
+/// under `kind`, each identified by its `key` field.
+fn describe(commands: &mut [BentoCommand], ctx: &Context, kind: &str, key: &str) {
+    if commands.is_empty() {
+        return;
+    }
+    let Some(stdout) = ctx
+        .limited(DESCRIPTION_TIMEOUT)
+        .stdout("brew", &["info", "--json=v2", "--installed"])
+    else {
+        return;
+    };
+    let descriptions = parse_info(&stdout, kind, key);
+    for cmd in commands {
+        cmd.description = descriptions.get(&cmd.name).cloned();
+    }
+}
+
+/// Map names to descriptions in `brew info --json=v2` output.
+fn parse_info(stdout: &str, kind: &str, key: &str) -> HashMap<String, String> {
+    let Ok(info) = serde_json::from_str::<Value>(stdout) else {
+        return HashMap::new();
+    };
+    info.get(kind)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            let name = package.get(key)?.as_str()?;
+            let description = package.get("desc")?.as_str()?;
+            Some((name.to_string(), description.to_string()))
+        })
+        .collect()
+}
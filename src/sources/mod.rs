@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::index::{Fingerprint, Index};
+use crate::{BentoCommand, Category};
+
+mod cargo;
+mod context;
+mod custom;
+mod go;
+mod homebrew;
+mod npm;
+mod path;
+mod pip;
+mod rc;
+mod runner;
+mod shell;
+mod yarn;
+
+pub use cargo::CargoSource;
+pub use context::Context;
+use context::deadline_after;
+pub use custom::CustomSource;
+pub use go::GoSource;
+pub use homebrew::{CaskSource, HomebrewSource};
+pub use npm::NpmSource;
+pub use path::PathSource;
+pub use pip::PipSource;
+pub use rc::RcDefinitions;
+pub use runner::{CommandRunner, FakeRunner, SystemRunner};
+pub use shell::{AliasSource, FunctionSource};
+pub use yarn::YarnSource;
+
+/// A provider of commands, such as the directories on `PATH` or a package manager.
+///
+/// Implement this trait and add it to a [`Registry`] to make bento search
+/// commands it does not know about out of the box.
+pub trait Source: Send + Sync {
+    /// Unique, short identifier for the source, e.g. `"homebrew"`.
+    fn name(&self) -> &str;
+
+    /// Category given to every command this source produces.
+    fn category(&self) -> Category;
+
+    /// Whether the source can be collected on this machine.
+    ///
+    /// Sources backed by a package manager should return `false` when the
+    /// tool is not installed, checking with [`Context::exists`] so a fake
+    /// runner can stand in for it. Unavailable sources are skipped by the
+    /// registry.
+    fn is_available(&self, _ctx: &Context) -> bool {
+        true
+    }
+
+    /// Collect every command this source currently provides.
+    ///
+    /// Processes should be spawned through `ctx` so they are killed when the
+    /// source exceeds its timeout.
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand>;
+
+    /// Files and directories whose modification times change when this
+    /// source's commands do, e.g. a package manager's install directory.
+    ///
+    /// Cached results are reused until one of these changes or the cache
+    /// expires. Sources returning no paths rely on expiry alone.
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// The settings that decide what this source collects, e.g. the command
+    /// a custom source runs.
+    ///
+    /// Cached results are discarded when these change, so edits to the
+    /// configuration show up without waiting for the cache to expire.
+    fn settings(&self) -> String {
+        String::new()
+    }
+
+    /// The version of the tool this source relies on, e.g. `"cargo 1.80.0"`,
+    /// as shown by `bento doctor`.
+    ///
+    /// Sources that do not wrap a tool return `None`.
+    fn version(&self, _ctx: &Context) -> Option<String> {
+        None
+    }
+}
+
+/// Timeout applied to sources without a timeout of their own.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest a source spends looking up descriptions for its commands.
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest a source spends looking up the version of its tool.
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Extra time given to a source after its deadline to notice and return.
+const GRACE_PERIOD: Duration = Duration::from_millis(250);
+
+/// How collecting a source went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceStatus {
+    /// The source listed this many commands.
+    Ok(usize),
+    /// The tool the source relies on is not installed.
+    NotInstalled,
+    /// The source failed, e.g. a command it ran exited unsuccessfully with
+    /// `code`, printing `stderr`. Whatever it listed is still used.
+    Failed { code: Option<i32>, stderr: String },
+    /// The source was killed for exceeding its timeout.
+    TimedOut,
+}
+
+impl fmt::Display for SourceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceStatus::Ok(1) => f.write_str("1 command"),
+            SourceStatus::Ok(count) => write!(f, "{count} commands"),
+            SourceStatus::NotInstalled => f.write_str("not installed"),
+            SourceStatus::Failed { code, stderr } => {
+                f.write_str("failed")?;
+                if let Some(code) = code {
+                    write!(f, " with exit code {code}")?;
+                }
+                match stderr.lines().find(|line| !line.trim().is_empty()) {
+                    Some(line) => write!(f, ": {}", line.trim()),
+                    None => Ok(()),
+                }
+            }
+            SourceStatus::TimedOut => f.write_str("timed out"),
+        }
+    }
+}
+
+/// How collecting one source of a [`Registry`] went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceReport {
+    pub name: String,
+    pub status: SourceStatus,
+    /// How long collecting took; zero when the commands came from the index.
+    pub elapsed: Duration,
+    /// Whether the commands came from the index instead of the source.
+    pub cached: bool,
+}
+
+/// The result of collecting every source in a [`Registry`].
+#[derive(Debug, Clone, Default)]
+pub struct Collection {
+    /// Commands from every source that finished in time, in registration order.
+    pub commands: Vec<BentoCommand>,
+    /// Names of the sources that were killed for exceeding their timeout.
+    pub timed_out: Vec<String>,
+    /// How each source went, in registration order.
+    pub reports: Vec<SourceReport>,
+}
+
+impl Collection {
+    /// Add the outcome of collecting a source.
+    fn add(&mut self, name: &str, outcome: Outcome, cached: bool) {
+        if outcome.status == SourceStatus::TimedOut {
+            self.timed_out.push(name.to_string());
+        }
+        self.commands.extend(outcome.commands);
+        self.reports.push(SourceReport {
+            name: name.to_string(),
+            status: outcome.status,
+            elapsed: outcome.elapsed,
+            cached,
+        });
+    }
+}
+
+/// What collecting a single source produced.
+struct Outcome {
+    status: SourceStatus,
+    commands: Vec<BentoCommand>,
+    elapsed: Duration,
+}
+
+/// An ordered set of [`Source`]s that are collected together.
+pub struct Registry {
+    sources: Vec<Arc<dyn Source>>,
+    timeout: Duration,
+    timeouts: HashMap<String, Duration>,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            timeouts: HashMap::new(),
+            runner: Arc::new(SystemRunner),
+        }
+    }
+}
+
+impl Registry {
+    /// Create a registry with no sources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with every source bento ships with.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(PathSource);
+        registry.register(HomebrewSource);
+        registry.register(CaskSource);
+        registry.register(PipSource);
+        registry.register(NpmSource);
+        registry.register(YarnSource);
+        registry.register(CargoSource);
+        registry.register(GoSource);
+        registry.register(AliasSource::new());
+        registry.register(FunctionSource::new());
+        registry
+    }
+
+    /// Add a source. Sources are collected in the order they were registered.
+    pub fn register<S: Source + 'static>(&mut self, source: S) -> &mut Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// Remove every source with the given name, returning whether any was removed.
+    pub fn disable(&mut self, name: &str) -> bool {
+        let before = self.sources.len();
+        self.sources.retain(|source| source.name() != name);
+        self.sources.len() != before
+    }
+
+    /// Put `source` in place of the registered source with the same name,
+    /// returning whether there was one.
+    pub fn replace<S: Source + 'static>(&mut self, source: S) -> bool {
+        match self
+            .sources
+            .iter_mut()
+            .find(|existing| existing.name() == source.name())
+        {
+            Some(existing) => {
+                *existing = Arc::new(source);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The registered sources, in collection order.
+    pub fn sources(&self) -> impl Iterator<Item = &dyn Source> {
+        self.sources.iter().map(|source| source.as_ref())
+    }
+
+    /// Set the timeout for sources without a timeout of their own.
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the timeout for the source with the given name.
+    pub fn set_source_timeout(&mut self, name: &str, timeout: Duration) -> &mut Self {
+        self.timeouts.insert(name.to_string(), timeout);
+        self
+    }
+
+    /// The timeout that applies to the source with the given name.
+    pub fn timeout_for(&self, name: &str) -> Duration {
+        self.timeouts.get(name).copied().unwrap_or(self.timeout)
+    }
+
+    /// Run the commands sources spawn with `runner` instead of as real
+    /// processes, e.g. a [`FakeRunner`] in tests.
+    pub fn set_runner<R: CommandRunner + 'static>(&mut self, runner: R) -> &mut Self {
+        self.runner = Arc::new(runner);
+        self
+    }
+
+    /// The version of every available source's tool, by source name, looked
+    /// up concurrently.
+    ///
+    /// Sources without a version, or whose tool did not report one in time,
+    /// are left out.
+    pub fn versions(&self) -> HashMap<String, String> {
+        thread::scope(|scope| {
+            let lookups: Vec<_> = self
+                .sources
+                .iter()
+                .filter_map(|source| {
+                    let timeout = self.timeout_for(source.name()).min(VERSION_TIMEOUT);
+                    let ctx = Context::with_runner(timeout, Arc::clone(&self.runner));
+                    source
+                        .is_available(&ctx)
+                        .then(|| (source.name(), scope.spawn(move || source.version(&ctx))))
+                })
+                .collect();
+            lookups
+                .into_iter()
+                .filter_map(|(name, lookup)| Some((name.to_string(), lookup.join().ok()??)))
+                .collect()
+        })
+    }
+
+    /// Collect the commands of every available source.
+    pub fn collect(&self) -> Vec<BentoCommand> {
+        self.collect_report().commands
+    }
+
+    /// Collect every available source concurrently, each on its own thread.
+    ///
+    /// Sources still running when their timeout elapses are reported in
+    /// [`Collection::timed_out`] and contribute no commands.
+    pub fn collect_report(&self) -> Collection {
+        let all: Vec<usize> = (0..self.sources.len()).collect();
+        let mut collection = Collection::default();
+        for (source, outcome) in self.sources.iter().zip(self.collect_sources(&all)) {
+            collection.add(source.name(), outcome, false);
+        }
+        collection
+    }
+
+    /// Like [`Registry::collect_report`], but reuse the commands cached in
+    /// `index` for sources whose watched paths and settings are unchanged.
+    ///
+    /// Sources that are re-collected successfully are written back to
+    /// `index`; those that failed are collected again next time.
+    pub fn collect_cached(&self, index: &mut Index) -> Collection {
+        let fingerprints: Vec<Fingerprint> = self
+            .sources
+            .iter()
+            .map(|source| Fingerprint::of(&source.watch_paths()).with_settings(source.settings()))
+            .collect();
+        let stale: Vec<usize> = self
+            .sources
+            .iter()
+            .zip(&fingerprints)
+            .enumerate()
+            .filter(|(_, (source, fingerprint))| index.get(source.name(), fingerprint).is_none())
+            .map(|(i, _)| i)
+            .collect();
+        let mut fresh: HashMap<usize, Outcome> = stale
+            .iter()
+            .copied()
+            .zip(self.collect_sources(&stale))
+            .collect();
+
+        let mut collection = Collection::default();
+        for (i, (source, fingerprint)) in self.sources.iter().zip(fingerprints).enumerate() {
+            match fresh.remove(&i) {
+                Some(outcome) => {
+                    if let SourceStatus::Ok(_) = outcome.status {
+                        index.insert(source.name(), fingerprint, outcome.commands.clone());
+                    }
+                    collection.add(source.name(), outcome, false);
+                }
+                None => {
+                    let commands = index
+                        .get(source.name(), &fingerprint)
+                        .unwrap_or_default()
+                        .to_vec();
+                    let outcome = Outcome {
+                        status: SourceStatus::Ok(commands.len()),
+                        commands,
+                        elapsed: Duration::ZERO,
+                    };
+                    collection.add(source.name(), outcome, true);
+                }
+            }
+        }
+        collection
+    }
+
+    /// Collect the sources at `indices` concurrently, returning how each went
+    /// in the same order.
+    fn collect_sources(&self, indices: &[usize]) -> Vec<Outcome> {
+        let started = Instant::now();
+        let (tx, rx) = mpsc::channel();
+        // How long each source's results are accepted for
+        let limits: Vec<Duration> = indices
+            .iter()
+            .map(|&index| {
+                let timeout = self.timeout_for(self.sources[index].name());
+                timeout.saturating_add(GRACE_PERIOD)
+            })
+            .collect();
+
+        for (slot, &index) in indices.iter().enumerate() {
+            let source = Arc::clone(&self.sources[index]);
+            let timeout = self.timeout_for(source.name());
+
+            let runner = Arc::clone(&self.runner);
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let ctx = Context::with_runner(timeout, runner);
+                let (status, commands) = if source.is_available(&ctx) {
+                    let commands = source.collect(&ctx);
+                    let status = match ctx.failure() {
+                        _ if ctx.timed_out() => SourceStatus::TimedOut,
+                        Some((code, stderr)) => SourceStatus::Failed { code, stderr },
+                        None => SourceStatus::Ok(commands.len()),
+                    };
+                    (status, commands)
+                } else {
+                    (SourceStatus::NotInstalled, Vec::new())
+                };
+                let _ = tx.send((slot, status, commands, started.elapsed()));
+            });
+        }
+        drop(tx);
+
+        let mut results: Vec<Option<Outcome>> = indices.iter().map(|_| None).collect();
+        let mut pending = vec![true; indices.len()];
+        // Wait for the sources still within their own deadline, if any
+        while let Some(deadline) = pending
+            .iter()
+            .zip(&limits)
+            .filter(|(pending, _)| **pending)
+            .map(|(_, &limit)| deadline_after(started, limit))
+            .max()
+            && let Some(remaining) = deadline.checked_duration_since(Instant::now())
+        {
+            let Ok((slot, status, commands, elapsed)) = rx.recv_timeout(remaining) else {
+                break;
+            };
+            pending[slot] = false;
+            if status != SourceStatus::TimedOut && elapsed <= limits[slot] {
+                results[slot] = Some(Outcome {
+                    status,
+                    commands,
+                    elapsed,
+                });
+            }
+        }
+        results
+            .into_iter()
+            .zip(indices)
+            .map(|(outcome, &index)| {
+                // Sources that did not answer in time contribute nothing
+                outcome.unwrap_or_else(|| Outcome {
+                    status: SourceStatus::TimedOut,
+                    commands: Vec::new(),
+                    elapsed: self.timeout_for(self.sources[index].name()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The first executable named `program` in the `PATH` directories.
+pub(crate) fn find_executable(program: &str) -> Option<PathBuf> {
+    env::var("PATH")
+        .ok()?
+        .split(':')
+        .map(|dir| Path::new(dir).join(program))
+        .find(|path| path.is_file())
+}
+
+/// The first non-empty line `program` prints to stdout when run with `args`,
+/// or `None` if it fails, e.g. to read a tool's version.
+pub(crate) fn first_line(ctx: &Context, program: &str, args: &[&str]) -> Option<String> {
+    let output = ctx.output(program, args).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// Whether an executable named `program` exists in one of the `PATH` directories.
+pub(crate) fn command_exists(program: &str) -> bool {
+    find_executable(program).is_some()
+}
+
+/// The user's home directory.
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
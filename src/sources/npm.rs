@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use super::{Context, Source, find_executable, first_line};
+use crate::{BentoCommand, Category};
+
+/// Globally installed npm packages.
+pub struct NpmSource;
+
+impl Source for NpmSource {
+    fn name(&self) -> &str {
+        "npm"
+    }
+
+    fn category(&self) -> Category {
+        Category::Npm
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("npm")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "npm", &["--version"]).map(|version| format!("npm {version}"))
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let Some(stdout) = ctx.stdout("npm", &["list", "-g", "--depth=0", "--parseable"]) else {
+            return Vec::new();
+        };
+        let mut commands = parse(&stdout, self.category());
+        let manifests = manifests(&stdout);
+        for cmd in &mut commands {
+            if let Some(manifest) = manifests.get(&cmd.name) {
+                cmd.description = manifest.description.clone();
+                cmd.provides = manifest.bin.clone();
+            }
+        }
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        // Global packages live in `<prefix>/lib/node_modules`, where npm
+        // itself is usually installed as `<prefix>/bin/npm`
+        let prefix = env::var_os("NPM_CONFIG_PREFIX")
+            .map(PathBuf::from)
+            .or_else(|| {
+                find_executable("npm").and_then(|npm| npm.parent()?.parent().map(PathBuf::from))
+            });
+        prefix
+            .map(|prefix| vec![prefix.join("lib").join("node_modules")])
+            .unwrap_or_default()
+    }
+}
+
+/// Parse the package directories printed by `npm list --parseable`, such
+/// as `/usr/lib/node_modules/@vue/cli`, skipping the global prefix itself.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    stdout
+        .lines()
+        .filter_map(|line| line.trim_end_matches('/').rsplit_once("/node_modules/"))
+        .map(|(_, name)| name)
+        .filter(|name| !name.is_empty())
+        .map(|name| BentoCommand::new(name.to_string(), category.clone()))
+        .collect()
+}
+
+/// What bento uses from a package's `package.json`.
+struct Manifest {
+    description: Option<String>,
+    /// Names of the executables the package links into npm's `bin` directory.
+    bin: Vec<String>,
+}
+
+/// The `package.json` of each package directory printed by
+/// `npm list --parseable`, keyed by package name.
+fn manifests(stdout: &str) -> HashMap<String, Manifest> {
+    stdout
+        .lines()
+        .filter_map(|dir| {
+            let manifest = fs::read_to_string(Path::new(dir).join("package.json")).ok()?;
+            let manifest: Value = serde_json::from_str(&manifest).ok()?;
+            let name = manifest.get("name")?.as_str()?;
+            let description = manifest
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|description| !description.is_empty())
+                .map(String::from);
+            let bin = match manifest.get("bin") {
+                // A single executable is named after the package, without its scope
+                Some(Value::String(_)) => vec![name.rsplit('/').next()?.to_string()],
+                Some(Value::Object(bins)) => bins.keys().cloned().collect(),
+                _ => Vec::new(),
+            };
+            Some((name.to_string(), Manifest { description, bin }))
+        })
+        .collect()
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use super::{Context, DESCRIPTION_TIMEOUT, Source};
+use crate::{BentoCommand, Category};
+
+/// Executables found in the directories listed in `PATH`.
+pub struct PathSource;
+
+impl Source for PathSource {
+    fn name(&self) -> &str {
+        "path"
+    }
+
+    fn category(&self) -> Category {
+        Category::Bin
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands = Vec::new();
+        let Ok(path_var) = env::var("PATH") else {
+            return commands;
+        };
+
+        for dir in path_var.split(':').filter(|dir| !dir.is_empty()) {
+            match executables(&absolute(Path::new(dir)), self.category()) {
+                Ok(found) => commands.extend(found),
+                // Missing directories are common and harmless
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => ctx.report_failure(None, format!("{dir}: {err}")),
+            }
+        }
+
+        let descriptions = descriptions(ctx);
+        for cmd in &mut commands {
+            cmd.description = descriptions.get(&cmd.name).cloned();
+        }
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        env::var("PATH")
+            .map(|path_var| path_var.split(':').map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The executables in `dir`, including symlinks to executables.
+pub(super) fn executables(dir: &Path, category: Category) -> io::Result<Vec<BentoCommand>> {
+    let entries = fs::read_dir(dir)?;
+    let mut commands = Vec::new();
+    for entry in entries.filter_map(Result::ok) {
+        let path = dir.join(entry.file_name());
+        // Follow symlinks, so linked executables are found as well
+        if let Ok(metadata) = fs::metadata(&path)
+            && metadata.is_file()
+            && metadata.permissions().mode() & 0o111 != 0
+            && let Ok(name) = entry.file_name().into_string()
+        {
+            let link_target = entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_symlink())
+                .then(|| fs::canonicalize(&path).ok())
+                .flatten();
+            commands.push(BentoCommand::new(name, category.clone()).with_path(path, link_target));
+        }
+    }
+    Ok(commands)
+}
+
+/// `path` relative to the current directory, if it is not absolute already.
+pub(super) fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// One-line summaries of commands from the man page index, keyed by name.
+fn descriptions(ctx: &Context) -> HashMap<String, String> {
+    // Descriptions are a nicety, so a slow man index gets its own shorter
+    // deadline rather than costing the source all of its commands
+    let lookup = ctx.limited(DESCRIPTION_TIMEOUT);
+    for (program, args) in [("apropos", &["."][..]), ("man", &["-k", "."])] {
+        if let Some(stdout) = lookup.stdout(program, args)
+            && !stdout.trim().is_empty()
+        {
+            return parse_whatis(&stdout);
+        }
+    }
+    HashMap::new()
+}
+
+/// Parse `apropos` output such as `ls (1) - list directory contents` or
+/// `git(1), git-scm(1) - the stupid content tracker`, keeping only pages
+/// from the command sections 1, 6 and 8.
+fn parse_whatis(stdout: &str) -> HashMap<String, String> {
+    let mut descriptions = HashMap::new();
+    for line in stdout.lines() {
+        let Some((names, description)) = line.split_once(" - ") else {
+            continue;
+        };
+        let description = description.trim();
+        if description.is_empty() {
+            continue;
+        }
+        for entry in names.split(',') {
+            let Some((name, section)) = entry.trim().split_once('(') else {
+                continue;
+            };
+            let name = name.trim();
+            if name.is_empty() || !section.starts_with(['1', '6', '8']) {
+                continue;
+            }
+            descriptions
+                .entry(name.to_string())
+                .or_insert_with(|| description.to_string());
+        }
+    }
+    descriptions
+}
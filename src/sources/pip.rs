@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Context, DESCRIPTION_TIMEOUT, Source, find_executable, first_line, home_dir};
+use crate::{BentoCommand, Category};
+
+/// Python packages installed with `pip`.
+pub struct PipSource;
+
+impl Source for PipSource {
+    fn name(&self) -> &str {
+        "pip"
+    }
+
+    fn category(&self) -> Category {
+        Category::Pip
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("pip")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        // `pip 24.0 from /path/to/pip (python 3.12)`
+        let line = first_line(ctx, "pip", &["--version"])?;
+        Some(line.split(" from ").next().unwrap_or(&line).to_string())
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands = ctx
+            .stdout("pip", &["list", "--format=freeze"])
+            .map(|stdout| parse(&stdout, self.category()))
+            .unwrap_or_default();
+        let distributions = distributions(ctx);
+        for cmd in &mut commands {
+            if let Some(distribution) = distributions.get(&normalize(&cmd.name)) {
+                cmd.description = distribution.summary.clone();
+                cmd.provides = distribution.scripts.clone();
+            }
+        }
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        site_packages()
+    }
+}
+
+/// The `site-packages` directories pip most likely installs into: those of
+/// the Python installation pip belongs to, and the user's own.
+fn site_packages() -> Vec<PathBuf> {
+    let mut lib_dirs = Vec::new();
+    if let Some(pip) = find_executable("pip")
+        && let Ok(pip) = fs::canonicalize(pip)
+        && let Some(prefix) = pip.parent().and_then(Path::parent)
+    {
+        lib_dirs.push(prefix.join("lib"));
+    }
+    if let Some(home) = home_dir() {
+        lib_dirs.push(home.join(".local").join("lib"));
+    }
+
+    let mut dirs = Vec::new();
+    for lib_dir in lib_dirs {
+        let Ok(entries) = fs::read_dir(&lib_dir) else {
+            continue;
+        };
+        let mut pythons: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("python"))
+            .map(|entry| entry.path())
+            .collect();
+        pythons.sort();
+        for python in pythons {
+            for packages in ["site-packages", "dist-packages"] {
+                let dir = python.join(packages);
+                if dir.is_dir() {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+    dirs
+}
+
+/// Parse `name==version` lines from `pip list --format=freeze`.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    stdout
+        .lines()
+        .filter_map(|line| line.split("==").next())
+        .map(|name| BentoCommand::new(name.to_string(), category.clone()))
+        .collect()
+}
+
+/// What bento uses from an installed distribution's `.dist-info` directory.
+#[derive(Default)]
+struct Distribution {
+    summary: Option<String>,
+    /// Names of the scripts it installed into a `bin` directory.
+    scripts: Vec<String>,
+}
+
+/// Installed distributions, keyed by normalized package name.
+fn distributions(ctx: &Context) -> HashMap<String, Distribution> {
+    let mut dirs = site_packages();
+    // `pip --version` names the directory pip itself is installed in, which
+    // finds the right one even when `pip` is a shim, e.g. from pyenv
+    if let Some(stdout) = ctx
+        .limited(DESCRIPTION_TIMEOUT)
+        .stdout("pip", &["--version"])
+        && let Some(dir) = parse_version(&stdout)
+        && !dirs.contains(&dir)
+    {
+        dirs.insert(0, dir);
+    }
+
+    let mut distributions = HashMap::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            if !entry.file_name().to_string_lossy().ends_with(".dist-info") {
+                continue;
+            }
+            let Ok(metadata) = fs::read_to_string(entry.path().join("METADATA")) else {
+                continue;
+            };
+            let Some((name, summary)) = parse_metadata(&metadata) else {
+                continue;
+            };
+            let scripts = fs::read_to_string(entry.path().join("RECORD"))
+                .map(|record| parse_record(&record))
+                .unwrap_or_default();
+            distributions
+                .entry(normalize(&name))
+                .or_insert(Distribution { summary, scripts });
+        }
+    }
+    distributions
+}
+
+/// The `Name` and `Summary` headers of a core metadata file.
+fn parse_metadata(metadata: &str) -> Option<(String, Option<String>)> {
+    let mut name = None;
+    let mut summary = None;
+    // Headers end at the first blank line, where the long description starts
+    for line in metadata.lines().take_while(|line| !line.is_empty()) {
+        if let Some(value) = line.strip_prefix("Name:") {
+            name = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("Summary:") {
+            summary = Some(value.trim().to_string());
+        }
+    }
+    let summary = summary.filter(|summary| !summary.is_empty() && summary != "UNKNOWN");
+    Some((name?, summary))
+}
+
+/// Names of the files a `RECORD` lists in a `bin` directory, such as
+/// `../../../bin/black,sha256=…,1234`.
+fn parse_record(record: &str) -> Vec<String> {
+    record
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .filter_map(|path| {
+            // Scripts live outside site-packages, so their paths climb out of it
+            let (dir, name) = path.strip_prefix("../")?.rsplit_once('/')?;
+            (dir.rsplit('/').next() == Some("bin") && !name.is_empty()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// The packages directory in `pip X.Y from <dir>/pip (python X.Y)`.
+fn parse_version(stdout: &str) -> Option<PathBuf> {
+    let (_, rest) = stdout.split_once(" from ")?;
+    let (pip_dir, _) = rest.rsplit_once(" (python")?;
+    Path::new(pip_dir.trim()).parent().map(Path::to_path_buf)
+}
+
+/// Normalize a package name as pip does, so `Foo_Bar` and `foo-bar` match.
+fn normalize(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
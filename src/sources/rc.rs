@@ -0,0 +1,567 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How many levels of `source`d files are followed.
+const MAX_DEPTH: usize = 8;
+
+/// Aliases and functions found by reading shell startup files.
+///
+/// The files are read, not run: definitions are found wherever they appear,
+/// even in branches the shell would skip, and anything computed at startup,
+/// such as aliases defined in a loop or with `eval`, is missed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RcDefinitions {
+    /// Alias names and what they expand to, in the order they were defined.
+    pub aliases: Vec<(String, String)>,
+    /// Function names and their source code, in the order they were defined.
+    pub functions: Vec<(String, String)>,
+    /// Every file and directory looked at, including missing ones, so that
+    /// creating one is noticed.
+    pub files: Vec<PathBuf>,
+}
+
+impl RcDefinitions {
+    /// Read the startup files in `home`, the files they `source`, and the
+    /// oh-my-zsh and prezto plugins and custom files they enable.
+    ///
+    /// `shell` is the user's shell, e.g. `/bin/zsh`. Only bash's or zsh's own
+    /// files are read when it is one of them; both sets otherwise.
+    pub fn read(home: &Path, shell: Option<&str>) -> Self {
+        let name = shell
+            .and_then(|shell| Path::new(shell).file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let (bash, zsh) = match name {
+            "bash" => (true, false),
+            "zsh" => (false, true),
+            _ => (true, true),
+        };
+
+        let mut reader = Reader::new(home);
+        if bash {
+            for file in [".profile", ".bash_profile", ".bashrc", ".bash_aliases"] {
+                reader.read_file(home.join(file), 0);
+            }
+        }
+        if zsh {
+            // .zshenv is where ZDOTDIR is usually set, moving the other files
+            reader.read_file(reader.zdotdir().join(".zshenv"), 0);
+            let zdotdir = reader.zdotdir();
+            for file in [".zprofile", ".zshrc"] {
+                reader.read_file(zdotdir.join(file), 0);
+            }
+            reader.read_oh_my_zsh();
+            reader.read_prezto(&zdotdir);
+        }
+        reader.definitions
+    }
+
+    /// The definitions in a single script, without following the files it
+    /// sources.
+    pub fn parse(script: &str) -> Self {
+        let mut reader = Reader::new(Path::new(""));
+        reader.parse(script, MAX_DEPTH);
+        reader.definitions
+    }
+}
+
+/// Reads startup files, keeping track of the variables they set and the
+/// framework plugins they enable.
+struct Reader {
+    home: PathBuf,
+    vars: HashMap<String, String>,
+    visited: HashSet<PathBuf>,
+    /// The files being read, innermost last, for `$0`.
+    stack: Vec<PathBuf>,
+    /// oh-my-zsh's `plugins=(...)`.
+    plugins: Vec<String>,
+    /// prezto's `zstyle ':prezto:load' pmodule ...`.
+    modules: Vec<String>,
+    /// prezto's `zstyle ':prezto:load' pmodule-dirs ...`.
+    module_dirs: Vec<PathBuf>,
+    definitions: RcDefinitions,
+}
+
+impl Reader {
+    fn new(home: &Path) -> Self {
+        let mut vars: HashMap<String, String> = env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+        vars.insert("HOME".to_string(), home.display().to_string());
+        Self {
+            home: home.to_path_buf(),
+            vars,
+            visited: HashSet::new(),
+            stack: Vec::new(),
+            plugins: Vec::new(),
+            modules: Vec::new(),
+            module_dirs: Vec::new(),
+            definitions: RcDefinitions::default(),
+        }
+    }
+
+    /// Where zsh reads its startup files from.
+    fn zdotdir(&self) -> PathBuf {
+        self.vars
+            .get("ZDOTDIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.home.clone())
+    }
+
+    fn watch(&mut self, path: &Path) {
+        if !self.definitions.files.iter().any(|file| file == path) {
+            self.definitions.files.push(path.to_path_buf());
+        }
+    }
+
+    fn read_file(&mut self, path: PathBuf, depth: usize) {
+        self.watch(&path);
+        let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if !self.visited.insert(key) {
+            return;
+        }
+        if let Ok(script) = fs::read_to_string(&path) {
+            self.stack.push(path);
+            self.parse(&script, depth);
+            self.stack.pop();
+        }
+    }
+
+    /// oh-my-zsh loads the plugins listed in `plugins`, then every `.zsh`
+    /// file in its custom directory.
+    fn read_oh_my_zsh(&mut self) {
+        let zsh = self
+            .vars
+            .get("ZSH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.home.join(".oh-my-zsh"));
+        let custom = self
+            .vars
+            .get("ZSH_CUSTOM")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| zsh.join("custom"));
+        if !zsh.is_dir() && !custom.is_dir() {
+            return;
+        }
+        for plugin in self.plugins.clone() {
+            let file = format!("{plugin}.plugin.zsh");
+            let candidates =
+                [&custom, &zsh].map(|dir| dir.join("plugins").join(&plugin).join(&file));
+            if let Some(path) = candidates.into_iter().find(|path| path.is_file()) {
+                self.read_file(path, 1);
+            }
+        }
+        self.watch(&custom);
+        for path in scripts_in(&custom) {
+            self.read_file(path, 1);
+        }
+    }
+
+    /// prezto reads `.zpreztorc`, then loads each module listed there from
+    /// the first module directory that has it.
+    fn read_prezto(&mut self, zdotdir: &Path) {
+        let prezto = zdotdir.join(".zprezto");
+        if !prezto.is_dir() {
+            return;
+        }
+        self.read_file(zdotdir.join(".zpreztorc"), 1);
+        let mut dirs = self.module_dirs.clone();
+        dirs.extend([prezto.join("contrib"), prezto.join("modules")]);
+        for dir in &dirs {
+            self.watch(dir);
+        }
+        for module in self.modules.clone() {
+            if let Some(path) = dirs
+                .iter()
+                .map(|dir| dir.join(&module).join("init.zsh"))
+                .find(|path| path.is_file())
+            {
+                self.read_file(path, 1);
+            }
+        }
+    }
+
+    fn parse(&mut self, script: &str, depth: usize) {
+        let lines: Vec<&str> = script.lines().collect();
+        let mut i = 0;
+        while i < lines.len() {
+            if let Some((name, rest)) = function_header(lines[i]) {
+                let end = function_end(&lines, i, rest);
+                if is_function_name(name) && !name.starts_with('_') {
+                    let source: Vec<&str> =
+                        lines[i..=end].iter().map(|line| line.trim_end()).collect();
+                    define(&mut self.definitions.functions, name, source.join("\n"));
+                }
+                i = end + 1;
+                continue;
+            }
+
+            let mut line = lines[i].trim().to_string();
+            while line.ends_with('\\') && i + 1 < lines.len() {
+                line.pop();
+                i += 1;
+                line.push(' ');
+                line.push_str(lines[i].trim());
+            }
+            if let Some(list) = line.strip_prefix("plugins=(") {
+                let mut list = list.to_string();
+                while !list.contains(')') && i + 1 < lines.len() {
+                    i += 1;
+                    list.push(' ');
+                    list.push_str(lines[i].trim());
+                }
+                let list = list.split(')').next().unwrap_or_default();
+                self.plugins = list
+                    .split_whitespace()
+                    .filter(|word| !word.starts_with('#'))
+                    .map(|word| word.trim_matches(|c| c == '\'' || c == '"').to_string())
+                    .collect();
+            } else {
+                for words in commands(&line) {
+                    self.run(&words, depth);
+                }
+            }
+            i += 1;
+        }
+    }
+
+    fn run(&mut self, words: &[String], depth: usize) {
+        let mut words = words;
+        // Skip keywords and prefixes that leave the command itself unchanged
+        while let Some((first, rest)) = words.split_first()
+            && matches!(
+                first.as_str(),
+                "then" | "do" | "else" | "{" | "(" | "!" | "builtin" | "command" | "noglob"
+            )
+        {
+            words = rest;
+        }
+        let Some((command, args)) = words.split_first() else {
+            return;
+        };
+        let options = || args.iter().filter(|arg| !arg.starts_with('-'));
+        match command.as_str() {
+            "alias" => {
+                for arg in options() {
+                    if let Some((name, value)) = arg.split_once('=')
+                        && is_alias_name(name)
+                    {
+                        define(&mut self.definitions.aliases, name, value.to_string());
+                    }
+                }
+            }
+            "unalias" => {
+                let names: Vec<&String> = options().collect();
+                self.definitions
+                    .aliases
+                    .retain(|(name, _)| !names.contains(&name));
+            }
+            "source" | "." => {
+                if depth < MAX_DEPTH
+                    && let Some(path) = args.first().and_then(|arg| self.resolve(arg))
+                {
+                    self.read_file(path, depth + 1);
+                }
+            }
+            "export" | "typeset" | "declare" => {
+                for arg in options() {
+                    self.assign(arg);
+                }
+            }
+            "zstyle" if args.first().map(String::as_str) == Some(":prezto:load") => {
+                match args.get(1).map(String::as_str) {
+                    Some("pmodule") => self.modules.extend(args[2..].iter().cloned()),
+                    Some("pmodule-dirs") => {
+                        let dirs: Vec<PathBuf> = args[2..]
+                            .iter()
+                            .filter_map(|dir| self.resolve(dir))
+                            .collect();
+                        self.module_dirs.extend(dirs);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {
+                for arg in words.iter().take_while(|word| word.contains('=')) {
+                    self.assign(arg);
+                }
+            }
+        }
+    }
+
+    /// Record a `NAME=value` assignment, so later paths using `$NAME` can be
+    /// followed.
+    fn assign(&mut self, word: &str) {
+        if let Some((name, value)) = word.split_once('=')
+            && !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && let Some(value) = self.expand(value)
+        {
+            self.vars.insert(name.to_string(), value);
+        }
+    }
+
+    /// The file a `source` argument refers to, or `None` if it depends on
+    /// something only running the shell could tell.
+    fn resolve(&self, arg: &str) -> Option<PathBuf> {
+        let path = self.expand(arg)?;
+        if path.is_empty() || path.contains(['*', '?', '[', '`']) {
+            return None;
+        }
+        // Startup files run in the home directory
+        Some(self.home.join(path))
+    }
+
+    /// `text` with a leading `~` and its `$NAME`, `${NAME}`, `${NAME:-default}`
+    /// and `${0:h}` references expanded, or `None` if it uses anything else.
+    fn expand(&self, text: &str) -> Option<String> {
+        let text = match text.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{rest}", self.home.display())
+            }
+            _ => text.to_string(),
+        };
+        let mut out = String::new();
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find('$') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+            if let Some(inner) = rest.strip_prefix('{') {
+                let end = inner.find('}')?;
+                out.push_str(&self.lookup(&inner[..end])?);
+                rest = &inner[end + 1..];
+            } else {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                out.push_str(&self.lookup(&rest[..end])?);
+                rest = &rest[end..];
+            }
+        }
+        out.push_str(rest);
+        Some(out)
+    }
+
+    /// The value of a parameter expression such as `HOME`, `ZDOTDIR:-$HOME`,
+    /// `0:A:h` or `0%/*`.
+    fn lookup(&self, expr: &str) -> Option<String> {
+        let end = expr
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(expr.len());
+        let (name, modifier) = expr.split_at(end);
+        let value = if name == "0" {
+            self.stack.last().map(|file| file.display().to_string())
+        } else {
+            self.vars
+                .get(name)
+                .filter(|value| !value.is_empty())
+                .cloned()
+        };
+        if let Some(default) = modifier.strip_prefix(":-") {
+            return value.or_else(|| self.expand(default));
+        }
+        let mut value = value?;
+        match modifier {
+            "%/*" => dirname(&mut value),
+            _ => {
+                for modifier in modifier.split(':').skip(1) {
+                    match modifier {
+                        "h" => dirname(&mut value),
+                        "a" | "A" => {}
+                        _ => return None,
+                    }
+                }
+                if !modifier.is_empty() && !modifier.starts_with(':') {
+                    return None;
+                }
+            }
+        }
+        Some(value)
+    }
+}
+
+fn dirname(path: &mut String) {
+    if let Some(end) = path.rfind('/') {
+        path.truncate(end.max(1));
+    }
+}
+
+/// Add or redefine a definition, keeping where it was first defined.
+fn define(definitions: &mut Vec<(String, String)>, name: &str, value: String) {
+    match definitions
+        .iter_mut()
+        .find(|(existing, _)| existing == name)
+    {
+        Some((_, existing)) => *existing = value,
+        None => definitions.push((name.to_string(), value)),
+    }
+}
+
+pub(super) fn is_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-~.".contains(c))
+}
+
+pub(super) fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-.:".contains(c))
+}
+
+/// The name and the rest of the line of a `name() ...`, `name () ...` or
+/// `function name ...` function definition.
+fn function_header(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("function ") {
+        let rest = rest.trim_start();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '(' || c == '{')
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(end);
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix("()").unwrap_or(rest);
+        return Some((name, rest.trim_start()));
+    }
+    let (name, rest) = line.split_once('(')?;
+    let name = name.trim_end();
+    let rest = rest.trim_start().strip_prefix(')')?;
+    is_function_name(name).then_some((name, rest.trim_start()))
+}
+
+/// The index of the line that ends the function whose header is at `start`,
+/// where `rest` is what follows the header on that line.
+///
+/// The body is a `{ ... }` or `( ... )` group, possibly starting on the next
+/// line, or a single command.
+fn function_end(lines: &[&str], start: usize, rest: &str) -> usize {
+    let mut group: Option<(char, char)> = None;
+    let mut depth = 0;
+    for (i, line) in std::iter::once(rest)
+        .chain(lines[start + 1..].iter().copied())
+        .enumerate()
+    {
+        for c in unquoted(line) {
+            match group {
+                None if c == '{' => group = Some(('{', '}')),
+                None if c == '(' => group = Some(('(', ')')),
+                None if c.is_whitespace() => continue,
+                None => return start + i,
+                Some((open, _)) if c == open => depth += 1,
+                Some((_, close)) if c == close => {
+                    if depth == 0 {
+                        return start + i;
+                    }
+                    depth -= 1;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    lines.len() - 1
+}
+
+/// The characters of `line` outside quotes and comments, with escaped
+/// characters left out.
+fn unquoted(line: &str) -> Vec<char> {
+    let mut out = Vec::new();
+    let mut chars = line.chars().peekable();
+    let mut word_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => chars.by_ref().take_while(|&c| c != '\'').for_each(drop),
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            '\\' => {
+                chars.next();
+            }
+            '#' if word_start => break,
+            _ => out.push(c),
+        }
+        word_start = c.is_whitespace() || ";&|({".contains(c);
+    }
+    out
+}
+
+/// Split a line into the words of each command on it, undoing quoting and
+/// dropping comments. Commands are separated by `;`, `&` and `|`.
+fn commands(line: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                in_word = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if chars.peek().is_some_and(|c| "\"\\$`".contains(*c)) => {
+                            word.extend(chars.next());
+                        }
+                        _ => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            }
+            '#' if !in_word => break,
+            ';' | '&' | '|' | ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                if c != ' ' && c != '\t' && !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            _ => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    commands
+}
+
+/// The `.zsh` files directly in `dir`, sorted by name as zsh globs them.
+fn scripts_in(dir: &Path) -> Vec<PathBuf> {
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zsh") && path.is_file())
+        .collect();
+    scripts.sort();
+    scripts
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running child process is polled for completion.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Runs the processes sources spawn through a [`Context`](super::Context).
+///
+/// [`SystemRunner`] runs real processes; [`FakeRunner`] replays recorded
+/// output, so sources can be tested without the tools they call.
+pub trait CommandRunner: Send + Sync {
+    /// Run `program` with `args` and capture its output.
+    ///
+    /// Implementations should stop the process, and return an error of kind
+    /// [`io::ErrorKind::TimedOut`], if it is still running at `deadline`.
+    fn run(&self, program: &str, args: &[&str], deadline: Instant) -> io::Result<Output>;
+
+    /// Whether `program` is installed, i.e. could be run.
+    ///
+    /// By default, whether an executable by that name is on `PATH`.
+    fn exists(&self, program: &str) -> bool {
+        super::command_exists(program)
+    }
+}
+
+/// Runs commands as child processes, killing them at the deadline.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str], deadline: Instant) -> io::Result<Output> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = read_to_end(child.stdout.take());
+        let stderr = read_to_end(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "command timed out"));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+}
+
+/// Drain a child pipe on its own thread so a chatty process cannot block on a full pipe.
+fn read_to_end<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Replies to commands with recorded output instead of running them.
+///
+/// Commands are matched exactly, program and arguments alike. Any other
+/// command fails as if the program were not installed, and only programs
+/// with recorded commands count as installed.
+#[derive(Debug, Clone, Default)]
+pub struct FakeRunner {
+    replies: HashMap<Vec<String>, Output>,
+}
+
+impl FakeRunner {
+    /// A runner with no recorded commands.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reply to `command`, a program followed by its arguments, with
+    /// `stdout` and a successful exit status.
+    pub fn with_stdout(self, command: &[&str], stdout: impl Into<String>) -> Self {
+        self.with_output(
+            command,
+            Output {
+                status: ExitStatus::from_raw(0),
+                stdout: stdout.into().into_bytes(),
+                stderr: Vec::new(),
+            },
+        )
+    }
+
+    /// Reply to `command` with `output`, e.g. to record a failure.
+    pub fn with_output(mut self, command: &[&str], output: Output) -> Self {
+        let command = command.iter().map(|arg| arg.to_string()).collect();
+        self.replies.insert(command, output);
+        self
+    }
+}
+
+impl CommandRunner for FakeRunner {
+    fn run(&self, program: &str, args: &[&str], _deadline: Instant) -> io::Result<Output> {
+        let command: Vec<String> = std::iter::once(program)
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        self.replies.get(&command).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no recorded output for `{}`", command.join(" ")),
+            )
+        })
+    }
+
+    fn exists(&self, program: &str) -> bool {
+        self.replies.keys().any(|command| command[0] == program)
+    }
+}
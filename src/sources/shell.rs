@@ -0,0 +1,278 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::rc::{RcDefinitions, is_alias_name, is_function_name};
+use super::{Context, Source, home_dir};
+use crate::{BentoCommand, Category};
+
+/// Aliases defined in the user's shell startup files.
+///
+/// The startup files are read rather than run, unless the interactive shell
+/// is enabled with [`AliasSource::with_interactive_shell`].
+#[derive(Debug, Clone, Default)]
+pub struct AliasSource {
+    startup: Startup,
+}
+
+impl AliasSource {
+    /// Read the current user's startup files, as given by `HOME` and `SHELL`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the startup files in `home` instead.
+    pub fn with_home(mut self, home: impl Into<PathBuf>) -> Self {
+        self.startup.home = Some(home.into());
+        self
+    }
+
+    /// Read the startup files of `shell`, e.g. `/bin/zsh`, instead.
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.startup.shell = Some(shell.into());
+        self
+    }
+
+    /// Also ask the shell, started interactively, for its aliases.
+    ///
+    /// This finds aliases the startup files define in ways that cannot be
+    /// read, at the cost of running them, which may print banners, start
+    /// agents or wait for input.
+    pub fn with_interactive_shell(mut self, interactive: bool) -> Self {
+        self.startup.interactive = interactive;
+        self
+    }
+
+    fn command(&self, name: &str) -> BentoCommand {
+        BentoCommand::new(name.to_string(), self.category())
+    }
+}
+
+impl Source for AliasSource {
+    fn name(&self) -> &str {
+        "alias"
+    }
+
+    fn category(&self) -> Category {
+        Category::Alias
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands: Vec<BentoCommand> = self
+            .startup
+            .read()
+            .aliases
+            .into_iter()
+            .map(|(name, value)| self.command(&name).with_definition(value))
+            .collect();
+
+        if let Some(stdout) = self.startup.interactive(ctx, "alias") {
+            for line in stdout.lines() {
+                if let Some((name, value)) = parse_alias(line) {
+                    let name = name.trim_matches('\'').trim_matches('"');
+                    if is_alias_name(name) && !commands.iter().any(|cmd| cmd.name == name) {
+                        commands.push(self.command(name).with_definition(value));
+                    }
+                }
+            }
+        }
+
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        self.startup.watch_paths()
+    }
+
+    fn settings(&self) -> String {
+        self.startup.settings()
+    }
+}
+
+/// Split a `name=value` or `alias name='value'` line into the alias name and
+/// what it expands to.
+fn parse_alias(line: &str) -> Option<(&str, String)> {
+    let (name, value) = line.split_once('=')?;
+    let name = name.trim_start_matches("alias ").trim();
+    (!name.is_empty()).then(|| (name, unquote(value.trim())))
+}
+
+/// Undo the shell quoting of an alias value as printed by `alias`, e.g.
+/// `'it'\''s'` becomes `it's`.
+fn unquote(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => out.extend(chars.by_ref().take_while(|&c| c != '\'')),
+            '\\' => out.extend(chars.next()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Functions defined in the user's shell startup files.
+///
+/// The startup files are read rather than run, unless the interactive shell
+/// is enabled with [`FunctionSource::with_interactive_shell`].
+#[derive(Debug, Clone, Default)]
+pub struct FunctionSource {
+    startup: Startup,
+}
+
+impl FunctionSource {
+    /// Read the current user's startup files, as given by `HOME` and `SHELL`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the startup files in `home` instead.
+    pub fn with_home(mut self, home: impl Into<PathBuf>) -> Self {
+        self.startup.home = Some(home.into());
+        self
+    }
+
+    /// Read the startup files of `shell`, e.g. `/bin/zsh`, instead.
+    pub fn with_shell(mut self, shell: impl Into<String>) -> Self {
+        self.startup.shell = Some(shell.into());
+        self
+    }
+
+    /// Also ask the shell, started interactively, for its functions, when it
+    /// is bash or zsh. See [`AliasSource::with_interactive_shell`].
+    pub fn with_interactive_shell(mut self, interactive: bool) -> Self {
+        self.startup.interactive = interactive;
+        self
+    }
+
+    fn command(&self, name: &str) -> BentoCommand {
+        BentoCommand::new(name.to_string(), self.category())
+    }
+}
+
+impl Source for FunctionSource {
+    fn name(&self) -> &str {
+        "function"
+    }
+
+    fn category(&self) -> Category {
+        Category::Function
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let mut commands: Vec<BentoCommand> = self
+            .startup
+            .read()
+            .functions
+            .into_iter()
+            .map(|(name, source)| self.command(&name).with_definition(source))
+            .collect();
+
+        let list = match self.startup.shell.as_deref() {
+            Some(shell) if shell.contains("zsh") => Some("functions"),
+            Some(shell) if shell.contains("bash") => Some("declare -f"),
+            _ => None,
+        };
+        if let Some(stdout) = list.and_then(|list| self.startup.interactive(ctx, list)) {
+            for (name, source) in parse_functions(&stdout) {
+                if !name.starts_with('_')
+                    && is_function_name(name)
+                    && !commands.iter().any(|cmd| cmd.name == name)
+                {
+                    commands.push(self.command(name).with_definition(source));
+                }
+            }
+        }
+
+        commands
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        self.startup.watch_paths()
+    }
+
+    fn settings(&self) -> String {
+        self.startup.settings()
+    }
+}
+
+/// Split the function definitions printed by bash's `declare -f` or zsh's
+/// `functions` into names and source code.
+///
+/// Each definition starts with an unindented `name () {` or `name ()` line
+/// and ends with an unindented `}`.
+fn parse_functions(stdout: &str) -> Vec<(&str, String)> {
+    let mut functions = Vec::new();
+    let mut current: Option<(&str, Vec<&str>)> = None;
+    for line in stdout.lines() {
+        if let Some((_, lines)) = &mut current {
+            lines.push(line.trim_end());
+            if line.trim_end() == "}"
+                && let Some((name, lines)) = current.take()
+            {
+                functions.push((name, lines.join("\n")));
+            }
+        } else if let Some(name) = line
+            .trim_end()
+            .trim_end_matches('{')
+            .trim_end()
+            .strip_suffix(" ()")
+            && !name.is_empty()
+            && !name.starts_with(char::is_whitespace)
+            && !name.contains(char::is_whitespace)
+        {
+            current = Some((name, vec![line.trim_end()]));
+        }
+    }
+    functions
+}
+
+/// Whose startup files the alias and function sources read, and whether
+/// they also run the shell.
+#[derive(Debug, Clone)]
+struct Startup {
+    home: Option<PathBuf>,
+    shell: Option<String>,
+    interactive: bool,
+}
+
+impl Default for Startup {
+    fn default() -> Self {
+        Self {
+            home: home_dir(),
+            shell: env::var("SHELL").ok().filter(|shell| !shell.is_empty()),
+            interactive: false,
+        }
+    }
+}
+
+impl Startup {
+    fn read(&self) -> RcDefinitions {
+        match &self.home {
+            Some(home) => RcDefinitions::read(home, self.shell.as_deref()),
+            None => RcDefinitions::default(),
+        }
+    }
+
+    /// The output of `script` run by the shell started interactively, if
+    /// that is enabled. A shell that cannot be started or fails is reported
+    /// with [`Context::report_failure`].
+    fn interactive(&self, ctx: &Context, script: &str) -> Option<String> {
+        let shell = self.shell.as_deref().filter(|_| self.interactive)?;
+        ctx.stdout(shell, &["-i", "-c", script])
+    }
+
+    /// Every startup file read, plus the shell itself so that switching
+    /// shells invalidates cached results.
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.shell.iter().map(PathBuf::from).collect();
+        paths.extend(self.read().files);
+        paths
+    }
+
+    /// Whether the shell is run, so that turning it on or off invalidates
+    /// cached results.
+    fn settings(&self) -> String {
+        format!("interactive_shell = {}", self.interactive)
+    }
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::path::PathBuf;
+
+use super::{Context, Source, first_line, home_dir};
+use crate::{BentoCommand, Category};
+
+/// Globally installed yarn packages.
+pub struct YarnSource;
+
+impl Source for YarnSource {
+    fn name(&self) -> &str {
+        "yarn"
+    }
+
+    fn category(&self) -> Category {
+        Category::Yarn
+    }
+
+    fn is_available(&self, ctx: &Context) -> bool {
+        ctx.exists("yarn")
+    }
+
+    fn version(&self, ctx: &Context) -> Option<String> {
+        first_line(ctx, "yarn", &["--version"]).map(|version| format!("yarn {version}"))
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        ctx.stdout("yarn", &["global", "list"])
+            .map(|stdout| parse(&stdout, self.category()))
+            .unwrap_or_default()
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")));
+        config_dir
+            .map(|dir| vec![dir.join("yarn").join("global").join("package.json")])
+            .unwrap_or_default()
+    }
+}
+
+/// Parse `yarn global list`: an `info "<package>@<version>" has binaries:`
+/// line per package, followed by the executables it installed as indented
+/// `- <name>` lines.
+fn parse(stdout: &str, category: Category) -> Vec<BentoCommand> {
+    let mut commands: Vec<BentoCommand> = Vec::new();
+    for line in stdout.lines() {
+        if let Some(info) = line.strip_prefix("info ") {
+            let spec = info
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .trim_matches('"');
+            // Scoped packages start with `@`, so the version follows the last one
+            if let Some((name, _)) = spec.rsplit_once('@')
+                && !name.is_empty()
+            {
+                commands.push(BentoCommand::new(name.to_string(), category.clone()));
+            }
+        } else if line.starts_with(' ')
+            && let Some(binary) = line.trim().strip_prefix("- ")
+            && let Some(package) = commands.last_mut()
+        {
+            package.provides.push(binary.trim().to_string());
+        }
+    }
+    commands
+}
@@ -1,40 +1,68 @@
-use bento::{get_commands, fuzzy_match, BentoCommand};
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Output};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use assert_cmd::Command;
+use bento::config::Config;
+use bento::doctor::{self, PathIssue, PathProblem};
+use bento::index::Fingerprint;
+use bento::init::Shell;
+use bento::output::{self, Format, Row};
+use bento::sources::{
+    AliasSource, CargoSource, CaskSource, CustomSource, FakeRunner, FunctionSource, GoSource,
+    HomebrewSource, NpmSource, PipSource, RcDefinitions, YarnSource,
+};
+use bento::resolve;
+use bento::picker::{Action, Picker};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use bento::{
+    get_commands, fuzzy_match, fuzzy_match_positions, match_command, BentoCommand, Category,
+    CategoryFilter, Context, Field, Index, Location, Palette, Registry, SearchMode, SearchQuery, Searcher,
+    Source, SourceStatus,
+};
 
 #[test]
 fn test_bento_command_creation() {
-    let cmd = BentoCommand {
-        name: "git".to_string(),
-        category: "bin".to_string(),
-    };
+    let mut cmd = BentoCommand::new("git", Category::Bin);
+    cmd.description = Some("the stupid content tracker".to_string());
     assert_eq!(cmd.name, "git");
-    assert_eq!(cmd.category, "bin");
+    assert_eq!(cmd.category, Category::Bin);
+    assert_eq!(cmd.description.as_deref(), Some("the stupid content tracker"));
+    assert!(cmd.also_in.is_empty());
 }
 
 #[test]
 fn test_bento_command_new() {
     let cmd = BentoCommand::new("test".to_string(), "bin".to_string());
     assert_eq!(cmd.name, "test");
-    assert_eq!(cmd.category, "bin");
+    assert_eq!(cmd.category, Category::Bin);
 }
 
 #[test]
 fn test_fuzzy_match_exact() {
-    assert_eq!(fuzzy_match("git", "git"), 6); // query.len() * 2
-    assert_eq!(fuzzy_match("test", "test"), 8);
+    // An exact match beats any longer name containing the query
+    assert!(fuzzy_match("git", "git") > fuzzy_match("git", "gitk"));
+    assert!(fuzzy_match("test", "test") > fuzzy_match("test", "pytest"));
 }
 
 #[test]
 fn test_fuzzy_match_contains() {
-    assert_eq!(fuzzy_match("git", "gitignore"), 6); // contains match
-    assert_eq!(fuzzy_match("bat", "batman"), 6);
-    assert_eq!(fuzzy_match("vim", "neovim"), 6);
+    assert!(fuzzy_match("git", "gitignore") > 0); // contains match
+    assert!(fuzzy_match("bat", "batman") > 0);
+    assert!(fuzzy_match("vim", "neovim") > 0);
 }
 
 #[test]
 fn test_fuzzy_match_partial() {
     // Should get some score for partial character matches
     let score = fuzzy_match("gt", "git");
-    assert!(score > 0 && score < 4); // Less than exact match
+    assert!(score > 0 && score < fuzzy_match("git", "git")); // Less than exact match
     
     let score = fuzzy_match("npm", "nodejs-package-manager");
     assert!(score > 0);
@@ -49,9 +77,9 @@ fn test_fuzzy_match_no_match() {
 
 #[test]
 fn test_fuzzy_match_case_insensitive() {
-    assert_eq!(fuzzy_match("GIT", "git"), 6);
-    assert_eq!(fuzzy_match("git", "GIT"), 6);
-    assert_eq!(fuzzy_match("PyThOn", "python"), 12);
+    assert_eq!(fuzzy_match("GIT", "git"), fuzzy_match("git", "git"));
+    assert_eq!(fuzzy_match("git", "GIT"), fuzzy_match("git", "git"));
+    assert_eq!(fuzzy_match("PyThOn", "python"), fuzzy_match("python", "python"));
 }
 
 #[test]
@@ -85,13 +113,10 @@ fn test_get_commands_integration() {
     // If we have commands, they should have valid categories
     for cmd in commands.iter().take(5) {
         assert!(!cmd.name.is_empty());
-        assert!(!cmd.category.is_empty());
+        assert!(!cmd.category.as_str().is_empty());
         
-        // Should be one of the valid categories
-        let valid_categories = vec![
-            "bin", "homebrew", "cask", "pip", "npm", "yarn", "cargo", "go", "alias", "function"
-        ];
-        assert!(valid_categories.contains(&cmd.category.as_str()));
+        // Should be one of the built-in categories
+        assert!(Category::builtin().contains(&cmd.category));
     }
 }
 
@@ -104,11 +129,9 @@ fn test_command_categories() {
     
     // Create test commands for each category
     for category in valid_categories {
-        let cmd = BentoCommand {
-            name: "test-command".to_string(),
-            category: category.to_string(),
-        };
-        assert_eq!(cmd.category, category);
+        let cmd = BentoCommand::new("test-command", category);
+        assert_eq!(cmd.category.as_str(), category);
+        assert!(Category::builtin().contains(&cmd.category));
     }
 }
 
@@ -132,75 +155,46 @@ fn test_fuzzy_match_performance() {
     let long_query = "a".repeat(100);
     let long_target = "b".repeat(100) + "a";
     let score = fuzzy_match(&long_query, &long_target);
-    // Should not panic and should return a valid usize
-    assert!(score <= long_query.len() * 2);
+    // Should not panic, and the query is longer than its only match
+    assert_eq!(score, 0);
 }
 
 #[test]
 fn test_command_filtering() {
-    let test_commands = vec![
-        BentoCommand {
-            name: "git".to_string(),
-            category: "bin".to_string(),
-        },
-        BentoCommand {
-            name: "python".to_string(),
-            category: "bin".to_string(),
-        },
-        BentoCommand {
-            name: "django".to_string(),
-            category: "pip".to_string(),
-        },
-        BentoCommand {
-            name: "ll".to_string(),
-            category: "alias".to_string(),
-        },
+    let test_commands = [
+        BentoCommand::new("git", Category::Bin),
+        BentoCommand::new("python", Category::Bin),
+        BentoCommand::new("django", Category::Pip),
+        BentoCommand::new("ll", Category::Alias),
     ];
 
     // Test that we can filter by category
-    let bin_commands: Vec<_> = test_commands
-        .iter()
-        .filter(|cmd| cmd.category == "bin")
-        .collect();
-    assert_eq!(bin_commands.len(), 2);
+    let searcher = Searcher::new(&test_commands);
+    let bin_commands = searcher.search(&SearchQuery::new("").with_categories(vec![Category::Bin]));
+    assert_eq!(bin_commands.results.len(), 2);
 
-    let pip_commands: Vec<_> = test_commands
-        .iter()
-        .filter(|cmd| cmd.category == "pip")
-        .collect();
-    assert_eq!(pip_commands.len(), 1);
-    assert_eq!(pip_commands[0].name, "django");
+    let pip_commands = searcher.search(&SearchQuery::new("").with_categories(vec![Category::Pip]));
+    assert_eq!(pip_commands.results.len(), 1);
+    assert_eq!(pip_commands.results[0].command.name, "django");
+
+    let others = searcher.search(&SearchQuery::new("").with_excludes(vec![Category::Bin]));
+    assert_eq!(others.total, 2);
 }
 
 #[test]
 fn test_scoring_and_sorting() {
-    let test_commands = vec![
-        BentoCommand {
-            name: "git".to_string(),
-            category: "bin".to_string(),
-        },
-        BentoCommand {
-            name: "gitignore".to_string(),
-            category: "bin".to_string(),
-        },
-        BentoCommand {
-            name: "gitlab".to_string(),
-            category: "bin".to_string(),
-        },
+    let test_commands = [
+        BentoCommand::new("git", Category::Bin),
+        BentoCommand::new("gitignore", Category::Bin),
+        BentoCommand::new("gitlab", Category::Bin),
     ];
 
-    let query = "git";
-    let mut scored: Vec<_> = test_commands
-        .iter()
-        .map(|cmd| (fuzzy_match(query, &cmd.name), cmd))
-        .filter(|(score, _)| *score > 0)
-        .collect();
-
-    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    let scored = Searcher::new(&test_commands).search(&SearchQuery::new("git")).results;
 
     // "git" should score highest
-    assert_eq!(scored[0].1.name, "git");
-    assert!(scored[0].0 >= scored[1].0);
+    assert_eq!(scored[0].command.name, "git");
+    assert!(scored[0].matched.score >= scored[1].matched.score);
+    assert_eq!(scored[0].matched.positions, vec![0, 1, 2]);
 }
 
 #[test]
@@ -217,41 +211,34 @@ fn test_unicode_handling() {
 #[test]
 fn test_edge_cases() {
     // Test various edge cases
-    assert_eq!(fuzzy_match("a", "a"), 2);
-    assert_eq!(fuzzy_match("ab", "ba"), 1); // Only 'a' matches in sequence
-    assert_eq!(fuzzy_match("abc", "cba"), 1); // Only 'a' matches in sequence
+    assert!(fuzzy_match("a", "a") > 0);
+    assert_eq!(fuzzy_match("ab", "ba"), 0); // Characters must appear in order
+    assert_eq!(fuzzy_match("abc", "cba"), 0);
 }
 
 #[test]
 fn test_search_workflow() {
     // Test the complete search workflow
-    let commands = vec![
+    let commands = [
         BentoCommand::new("git".to_string(), "bin".to_string()),
         BentoCommand::new("github-cli".to_string(), "homebrew".to_string()),
         BentoCommand::new("gitignore".to_string(), "npm".to_string()),
         BentoCommand::new("pytest".to_string(), "pip".to_string()),
     ];
 
-    let query = "git";
-    let mut results: Vec<_> = commands
-        .iter()
-        .map(|cmd| (fuzzy_match(query, &cmd.name), cmd))
-        .filter(|(score, _)| *score > 0)
-        .collect();
+    let results = Searcher::new(&commands).search(&SearchQuery::new("git")).results;
 
-    results.sort_by(|a, b| b.0.cmp(&a.0));
-    
     assert!(!results.is_empty());
     // All results should contain git-related commands
-    for (score, cmd) in results {
-        assert!(score > 0);
-        assert!(cmd.name.to_lowercase().contains("git"));
+    for result in results {
+        assert!(result.matched.score > 0);
+        assert!(result.command.name.to_lowercase().contains("git"));
     }
 }
 
 #[test]
 fn test_category_filtering() {
-    let commands = vec![
+    let commands = [
         BentoCommand::new("brew".to_string(), "homebrew".to_string()),
         BentoCommand::new("npm".to_string(), "bin".to_string()),
         BentoCommand::new("pip".to_string(), "bin".to_string()),
@@ -259,18 +246,2321 @@ fn test_category_filtering() {
     ];
 
     // Test filtering by category
-    let bin_only: Vec<_> = commands
-        .iter()
-        .filter(|cmd| cmd.category == "bin")
-        .collect();
-    
-    assert_eq!(bin_only.len(), 3);
-    
-    let homebrew_only: Vec<_> = commands
-        .iter()
-        .filter(|cmd| cmd.category == "homebrew")
+    let searcher = Searcher::new(&commands);
+    let bin_only = searcher.search(&SearchQuery::new("").with_categories(vec![Category::Bin]));
+
+    // An empty query keeps the commands in their original order
+    let names: Vec<_> = bin_only.results.iter().map(|result| result.command.name.as_str()).collect();
+    assert_eq!(names, ["npm", "pip", "cargo"]);
+
+    let homebrew_only =
+        searcher.search(&SearchQuery::new("").with_categories(vec![Category::Homebrew]));
+
+    assert_eq!(homebrew_only.results.len(), 1);
+    assert_eq!(homebrew_only.results[0].command.name, "brew");
+}
+
+struct FakeSource {
+    name: &'static str,
+    available: bool,
+    names: Vec<&'static str>,
+}
+
+impl Source for FakeSource {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn category(&self) -> Category {
+        Category::Other("fake".to_string())
+    }
+
+    fn is_available(&self, _ctx: &Context) -> bool {
+        self.available
+    }
+
+    fn collect(&self, _ctx: &Context) -> Vec<BentoCommand> {
+        self.names
+            .iter()
+            .map(|name| BentoCommand::new(*name, self.category()))
+            .collect()
+    }
+}
+
+#[test]
+fn test_registry_collects_registered_sources_in_order() {
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "first", available: true, names: vec!["a", "b"] })
+        .register(FakeSource { name: "second", available: true, names: vec!["c"] });
+
+    let names: Vec<_> = registry.collect().into_iter().map(|cmd| cmd.name).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_registry_skips_unavailable_sources() {
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "missing", available: false, names: vec!["a"] })
+        .register(FakeSource { name: "present", available: true, names: vec!["b"] });
+
+    let commands = registry.collect();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].name, "b");
+    assert_eq!(commands[0].category, Category::Other("fake".to_string()));
+}
+
+#[test]
+fn test_registry_disable() {
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "keep", available: true, names: vec!["a"] })
+        .register(FakeSource { name: "drop", available: true, names: vec!["b"] });
+
+    assert!(registry.disable("drop"));
+    assert!(!registry.disable("drop"));
+    assert_eq!(registry.sources().count(), 1);
+    assert_eq!(registry.collect()[0].name, "a");
+}
+
+#[test]
+fn test_builtin_registry_sources() {
+    let registry = Registry::builtin();
+    let names: Vec<_> = registry.sources().map(|source| source.name().to_string()).collect();
+    for expected in ["path", "homebrew", "cask", "pip", "npm", "yarn", "cargo", "go", "alias", "function"] {
+        assert!(names.iter().any(|name| name == expected), "missing source {expected}");
+    }
+}
+
+#[test]
+fn test_category_round_trip() {
+    for category in Category::builtin() {
+        let parsed: Category = category.to_string().parse().unwrap();
+        assert_eq!(&parsed, category);
+    }
+    assert_eq!("Homebrew".parse::<Category>(), Ok(Category::Homebrew));
+}
+
+#[test]
+fn test_category_other() {
+    let category: Category = "plugin".parse().unwrap();
+    assert_eq!(category, Category::Other("plugin".to_string()));
+    assert_eq!(category.to_string(), "plugin");
+    assert!(!Category::builtin().contains(&category));
+}
+
+#[test]
+fn test_category_parse_rejects_invalid_names() {
+    assert!("".parse::<Category>().is_err());
+    assert!("  ".parse::<Category>().is_err());
+    assert!("bin,pip".parse::<Category>().is_err());
+}
+
+struct SleepySource;
+
+impl Source for SleepySource {
+    fn name(&self) -> &str {
+        "sleepy"
+    }
+
+    fn category(&self) -> Category {
+        Category::Other("sleepy".to_string())
+    }
+
+    fn collect(&self, ctx: &Context) -> Vec<BentoCommand> {
+        let _ = ctx.output("sleep", &["5"]);
+        vec![BentoCommand::new("late", self.category())]
+    }
+}
+
+#[test]
+fn test_registry_reports_timed_out_sources() {
+    let mut registry = Registry::new();
+    registry
+        .register(SleepySource)
+        .register(FakeSource { name: "fast", available: true, names: vec!["a"] })
+        .set_source_timeout("sleepy", Duration::from_millis(100));
+
+    let started = Instant::now();
+    let collection = registry.collect_report();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(collection.timed_out, vec!["sleepy".to_string()]);
+    assert_eq!(collection.commands.len(), 1);
+    assert_eq!(collection.commands[0].name, "a");
+}
+
+/// A source that takes `nap` to finish, without checking its deadline.
+struct DozingSource {
+    name: &'static str,
+    nap: Duration,
+}
+
+impl Source for DozingSource {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn category(&self) -> Category {
+        Category::Other("dozing".to_string())
+    }
+
+    fn collect(&self, _ctx: &Context) -> Vec<BentoCommand> {
+        std::thread::sleep(self.nap);
+        vec![BentoCommand::new(self.name, self.category())]
+    }
+}
+
+#[test]
+fn test_registry_enforces_each_source_timeout() {
+    let mut registry = Registry::new();
+    registry
+        .register(DozingSource { name: "slow", nap: Duration::from_millis(700) })
+        .register(DozingSource { name: "patient", nap: Duration::from_millis(1000) })
+        .set_timeout(Duration::from_secs(5))
+        .set_source_timeout("slow", Duration::from_millis(100));
+
+    // The slow source finishes while the registry still waits for the
+    // patient one, but past its own timeout
+    let collection = registry.collect_report();
+    assert_eq!(collection.timed_out, vec!["slow".to_string()]);
+    assert_eq!(names(&collection.commands), ["patient"]);
+}
+
+#[test]
+fn test_registry_timeouts() {
+    let mut registry = Registry::new();
+    registry
+        .set_timeout(Duration::from_secs(3))
+        .set_source_timeout("brew", Duration::from_secs(1));
+
+    assert_eq!(registry.timeout_for("brew"), Duration::from_secs(1));
+    assert_eq!(registry.timeout_for("pip"), Duration::from_secs(3));
+}
+
+#[test]
+fn test_context_kills_commands_past_deadline() {
+    let ctx = Context::new(Duration::from_millis(50));
+    let started = Instant::now();
+    let err = ctx.output("sleep", &["5"]).unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::TimedOut);
+    assert!(ctx.timed_out());
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_context_captures_output() {
+    let ctx = Context::new(Duration::from_secs(5));
+    assert_eq!(ctx.stdout("echo", &["hello"]).as_deref(), Some("hello\n"));
+    assert!(!ctx.timed_out());
+}
+
+struct CountingSource {
+    calls: Arc<AtomicUsize>,
+    watched: PathBuf,
+}
+
+impl Source for CountingSource {
+    fn name(&self) -> &str {
+        "counting"
+    }
+
+    fn category(&self) -> Category {
+        Category::Bin
+    }
+
+    fn collect(&self, _ctx: &Context) -> Vec<BentoCommand> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        vec![BentoCommand::new("counted", Category::Bin)]
+    }
+
+    fn watch_paths(&self) -> Vec<PathBuf> {
+        vec![self.watched.clone()]
+    }
+}
+
+#[test]
+fn test_index_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache").join("index.json");
+    let fingerprint = Fingerprint::of(&[dir.path().to_path_buf()]);
+
+    let mut index = Index::new();
+    assert!(!index.is_changed());
+    index.insert("path", fingerprint.clone(), vec![BentoCommand::new("git", Category::Bin)]);
+    assert!(index.is_changed());
+    index.save(&path).unwrap();
+
+    let loaded = Index::load(&path);
+    assert!(!loaded.is_changed());
+    let cached = loaded.get("path", &fingerprint).unwrap();
+    assert_eq!(cached, [BentoCommand::new("git", Category::Bin)]);
+    assert!(loaded.get("pip", &fingerprint).is_none());
+}
+
+#[test]
+fn test_index_load_ignores_corrupt_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.json");
+    fs::write(&path, "not json").unwrap();
+
+    let index = Index::load(&path);
+    assert!(index.get("path", &Fingerprint::of(&[])).is_none());
+}
+
+#[test]
+fn test_index_invalidation() {
+    let dir = tempfile::tempdir().unwrap();
+    let watched = dir.path().join("watched");
+    let before = Fingerprint::of(std::slice::from_ref(&watched));
+
+    let mut index = Index::new();
+    index.insert("path", before.clone(), Vec::new());
+    assert!(index.get("path", &before).is_some());
+
+    // Creating a watched path changes the fingerprint
+    fs::write(&watched, "").unwrap();
+    let after = Fingerprint::of(std::slice::from_ref(&watched));
+    assert_ne!(before, after);
+    assert!(index.get("path", &after).is_none());
+
+    // Expired entries are not reused
+    index.set_ttl(Duration::ZERO);
+    assert!(index.get("path", &before).is_none());
+}
+
+#[test]
+fn test_registry_collect_cached_reuses_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut registry = Registry::new();
+    registry.register(CountingSource {
+        calls: Arc::clone(&calls),
+        watched: dir.path().to_path_buf(),
+    });
+
+    let mut index = Index::new();
+    let first = registry.collect_cached(&mut index);
+    let second = registry.collect_cached(&mut index);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.commands, second.commands);
+
+    // Adding a file bumps the watched directory's mtime
+    fs::write(dir.path().join("new-tool"), "").unwrap();
+    registry.collect_cached(&mut index);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_collect_cached_notices_changed_custom_sources() {
+    let plugin = Category::Other("plugin".to_string());
+    let cached = |command: &str, category: &Category, index: &mut Index| {
+        let mut registry = Registry::new();
+        registry.register(CustomSource::command("plugins", category.clone(), command));
+        registry.collect_cached(index).commands
+    };
+
+    let mut index = Index::new();
+    let first = cached("echo login", &plugin, &mut index);
+    assert_eq!(names(&first), ["login"]);
+
+    // A command source watches nothing, so only its settings tell it changed
+    let edited = cached("echo logout", &plugin, &mut index);
+    assert_eq!(names(&edited), ["logout"]);
+
+    let acme = Category::Other("acme".to_string());
+    let recategorized = cached("echo logout", &acme, &mut index);
+    assert_eq!(recategorized[0].category, acme);
+}
+
+#[test]
+fn test_collect_cached_notices_interactive_shell_setting() {
+    let home = tempfile::tempdir().unwrap();
+    fs::write(home.path().join(".zshrc"), "alias gs='git status'\n").unwrap();
+    let cached = |interactive: bool, index: &mut Index| {
+        let mut registry = Registry::new();
+        registry
+            .register(
+                AliasSource::new()
+                    .with_home(home.path())
+                    .with_shell("/bin/zsh")
+                    .with_interactive_shell(interactive),
+            )
+            .set_runner(FakeRunner::new().with_stdout(&["/bin/zsh", "-i", "-c", "alias"], "ll='ls -l'\n"));
+        registry.collect_cached(index).commands
+    };
+
+    let mut index = Index::new();
+    assert_eq!(names(&cached(false, &mut index)), ["gs"]);
+    assert_eq!(names(&cached(true, &mut index)), ["gs", "ll"]);
+    assert_eq!(names(&cached(false, &mut index)), ["gs"]);
+}
+
+#[test]
+fn test_fuzzy_match_ranks_exact_then_prefix_then_substring() {
+    let exact = fuzzy_match("git", "git");
+    let prefix = fuzzy_match("git", "gitk");
+    let inner = fuzzy_match("git", "legit");
+    assert!(exact > prefix);
+    assert!(prefix > inner);
+}
+
+#[test]
+fn test_fuzzy_match_word_boundaries() {
+    // Matches right after `-`, `_` or `.` beat matches inside a word
+    assert!(fuzzy_match("git", "my-git") > fuzzy_match("git", "mygit"));
+    assert!(fuzzy_match("rc", "x_rc") > fuzzy_match("rc", "xarc"));
+    assert!(fuzzy_match("dc", "docker-compose") > fuzzy_match("dc", "dockerxcompose"));
+}
+
+#[test]
+fn test_fuzzy_match_camel_case() {
+    assert!(fuzzy_match("fb", "fooBar") > fuzzy_match("fb", "foobar"));
+}
+
+#[test]
+fn test_fuzzy_match_prefers_consecutive_characters() {
+    assert!(fuzzy_match("abc", "abcxxx") > fuzzy_match("abc", "axbxcx"));
+    // Wider gaps cost more
+    assert!(fuzzy_match("ac", "abc") > fuzzy_match("ac", "abbbbbc"));
+}
+
+#[test]
+fn test_fuzzy_match_penalizes_long_targets() {
+    assert!(fuzzy_match("vim", "vimdiff") > fuzzy_match("vim", "vimdiff-extended"));
+}
+
+#[test]
+fn test_fuzzy_match_ignores_diacritics() {
+    assert_eq!(fuzzy_match("cafe", "café"), fuzzy_match("cafe", "cafe"));
+}
+
+#[test]
+fn test_fuzzy_match_positions() {
+    let m = fuzzy_match_positions("git", "git").unwrap();
+    assert_eq!(m.positions, vec![0, 1, 2]);
+    assert_eq!(m.score, fuzzy_match("git", "git"));
+
+    // Boundaries are preferred over earlier scattered characters
+    let m = fuzzy_match_positions("gsh", "git-shell").unwrap();
+    assert_eq!(m.positions, vec![0, 4, 5]);
+
+    // Consecutive characters are preferred over a scattered alignment
+    let m = fuzzy_match_positions("vim", "v-i-vim").unwrap();
+    assert_eq!(m.positions, vec![4, 5, 6]);
+}
+
+#[test]
+fn test_fuzzy_match_positions_are_char_indices() {
+    let m = fuzzy_match_positions("fe", "café").unwrap();
+    assert_eq!(m.positions, vec![2, 3]);
+}
+
+#[test]
+fn test_fuzzy_match_positions_no_match() {
+    assert!(fuzzy_match_positions("xyz", "git").is_none());
+    assert!(fuzzy_match_positions("", "git").is_none());
+}
+
+fn write_to_string(format: Format, rows: &[Row<'_>]) -> String {
+    let mut out = Vec::new();
+    output::write_rows(&mut out, format, rows).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_output_json() {
+    let git = BentoCommand::new("git", Category::Bin);
+    let rows = [Row { command: &git, score: 42 }];
+
+    let json: serde_json::Value = serde_json::from_str(&write_to_string(Format::Json, &rows)).unwrap();
+    assert_eq!(json, serde_json::json!([{ "name": "git", "category": "bin", "score": 42 }]));
+}
+
+#[test]
+fn test_output_ndjson() {
+    let git = BentoCommand::new("git", Category::Bin);
+    let ll = BentoCommand::new("ll", Category::Alias);
+    let rows = [Row { command: &git, score: 2 }, Row { command: &ll, score: 1 }];
+
+    let out = write_to_string(Format::Ndjson, &rows);
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[1], r#"{"name":"ll","category":"alias","score":1}"#);
+}
+
+#[test]
+fn test_output_csv_quotes_fields() {
+    let odd = BentoCommand::new("a,\"b\"", Category::Bin);
+    let rows = [Row { command: &odd, score: 1 }];
+
+    let out = write_to_string(Format::Csv, &rows);
+    assert_eq!(out, "name,category,also_in,description,definition,path,link_target,also_at,provides,packages,score\n\"a,\"\"b\"\"\",bin,,,,,,,,,1\n");
+}
+
+#[test]
+fn test_output_tsv_escapes_tabs() {
+    let odd = BentoCommand::new("a\tb", Category::Other("my tools".to_string()));
+    let rows = [Row { command: &odd, score: 1 }];
+
+    let out = write_to_string(Format::Tsv, &rows);
+    assert_eq!(out, "name\tcategory\talso_in\tdescription\tdefinition\tpath\tlink_target\talso_at\tprovides\tpackages\tscore\na\\tb\tmy tools\t\t\t\t\t\t\t\t\t1\n");
+}
+
+#[test]
+fn test_bento_command_serde_round_trip() {
+    let cmd = BentoCommand::new("deploy", Category::Other("plugin".to_string()));
+    let json = serde_json::to_string(&cmd).unwrap();
+    assert_eq!(serde_json::from_str::<BentoCommand>(&json).unwrap(), cmd);
+}
+
+/// The `bento` binary, kept away from the user's own setup: `HOME` is
+/// `home`, `BENTO_CONFIG` names a file in it that does not exist and
+/// `ZDOTDIR` is unset. Tests needing a config or startup files write them to
+/// `home` or set the variables again.
+fn bento(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("bento").unwrap();
+    cmd.env("HOME", home)
+        .env("BENTO_CONFIG", home.join("config.toml"))
+        .env_remove("ZDOTDIR");
+    cmd
+}
+
+#[test]
+fn test_cli_rejects_unknown_format() {
+    let home = tempfile::tempdir().unwrap();
+    bento(home.path())
+        .args(["--format", "xml", "git"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("possible values"));
+}
+
+#[test]
+fn test_cli_rejects_invalid_timeout() {
+    let home = tempfile::tempdir().unwrap();
+    for timeout in ["-1", "1e30", "nan", "inf", "soon"] {
+        bento(home.path())
+            .args([&format!("--timeout={timeout}"), "git"])
+            .assert()
+            .code(2)
+            .stderr(predicates::str::contains("invalid value"));
+    }
+}
+
+#[test]
+fn test_cli_accepts_huge_timeout() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let tool = bin.path().join("tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    // Too long to add to the clock, so it is as good as no timeout
+    bento(cache.path())
+        .args(["--timeout", "1e19", "tool"])
+        .env("PATH", bin.path())
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("tool"));
+}
+
+#[test]
+fn test_cli_saves_index_only_when_it_changes() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let tool = bin.path().join("tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    let index = cache.path().join("bento").join("index.json");
+    let run = || {
+        bento(cache.path())
+            .arg("tool")
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .assert()
+            .success();
+        fs::metadata(&index).unwrap().modified().unwrap()
+    };
+
+    let written = run();
+    // Every source is cached now, so there is nothing to write
+    assert_eq!(run(), written);
+    fs::write(bin.path().join("other"), "").unwrap();
+    assert_ne!(run(), written);
+}
+
+fn picker_commands() -> Vec<BentoCommand> {
+    vec![
+        BentoCommand::new("git", Category::Bin),
+        BentoCommand::new("gitk", Category::Bin),
+        BentoCommand::new("gst", Category::Alias),
+        BentoCommand::new("ls", Category::Bin),
+    ]
+}
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+#[test]
+fn test_picker_filters_as_you_type() {
+    let commands = picker_commands();
+    let mut picker = Picker::new(&commands, "");
+    assert_eq!(picker.results().len(), 4);
+
+    for c in "gi".chars() {
+        assert_eq!(picker.handle_key(key(KeyCode::Char(c))), Action::Continue);
+    }
+    assert_eq!(picker.query(), "gi");
+    let names: Vec<_> = picker.results().iter().map(|result| result.command.name.as_str()).collect();
+    assert_eq!(names, vec!["git", "gitk"]);
+
+    picker.handle_key(key(KeyCode::Backspace));
+    assert_eq!(picker.query(), "g");
+    assert_eq!(picker.results().len(), 3);
+}
+
+#[test]
+fn test_picker_navigation_and_accept() {
+    let commands = picker_commands();
+    let mut picker = Picker::new(&commands, "git");
+    assert_eq!(picker.selected().unwrap().name, "git");
+
+    picker.handle_key(key(KeyCode::Down));
+    assert_eq!(picker.selected().unwrap().name, "gitk");
+    // Moving past the end stays on the last result
+    picker.handle_key(key(KeyCode::Down));
+    assert_eq!(picker.selected().unwrap().name, "gitk");
+    picker.handle_key(key(KeyCode::Up));
+    assert_eq!(picker.selected().unwrap().name, "git");
+
+    assert_eq!(picker.handle_key(key(KeyCode::Enter)), Action::Accept);
+    assert_eq!(picker.handle_key(key(KeyCode::Esc)), Action::Abort);
+}
+
+#[test]
+fn test_picker_category_toggles() {
+    let commands = picker_commands();
+    let mut picker = Picker::new(&commands, "g");
+    let categories: Vec<_> = picker.categories().iter().map(|(c, _)| c.clone()).collect();
+    assert_eq!(categories, vec![Category::Bin, Category::Alias]);
+
+    // Alt-1 toggles the first category
+    picker.handle_key(KeyEvent::new(KeyCode::Char('1'), KeyModifiers::ALT));
+    let names: Vec<_> = picker.results().iter().map(|result| result.command.name.as_str()).collect();
+    assert_eq!(names, vec!["gst"]);
+
+    // Tab cycles from one category at a time to all of them and around again
+    picker.handle_key(key(KeyCode::Tab));
+    assert_eq!(picker.results().len(), 3);
+    picker.handle_key(key(KeyCode::Tab));
+    assert!(picker.results().iter().all(|result| result.command.category == Category::Bin));
+    picker.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    assert_eq!(picker.results().len(), 3);
+}
+
+#[test]
+fn test_shell_round_trip() {
+    for shell in Shell::ALL {
+        assert_eq!(shell.as_str().parse::<Shell>(), Ok(shell));
+        assert!(shell.script().contains("bento pick"));
+    }
+    assert_eq!("ZSH".parse::<Shell>(), Ok(Shell::Zsh));
+    assert!("tcsh".parse::<Shell>().is_err());
+}
+
+#[test]
+fn test_cli_init_prints_script() {
+    let home = tempfile::tempdir().unwrap();
+    bento(home.path())
+        .args(["init", "zsh"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("bindkey"))
+        .stdout(predicates::str::contains("bento pick"));
+
+    bento(home.path())
+        .args(["init", "tcsh"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_bash_widget_replaces_word_before_cursor() {
+    // Stand-in for bento that picks git-status when asked for "gi"
+    let dir = tempfile::tempdir().unwrap();
+    let fake = dir.path().join("bento");
+    fs::write(&fake, "#!/bin/sh\n[ \"$3\" = gi ] && echo git-status\n").unwrap();
+    fs::set_permissions(&fake, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let script = format!(
+        "{}\nREADLINE_LINE='sudo gi --help'; READLINE_POINT=7; __bento_widget\n\
+         echo \"$READLINE_LINE|$READLINE_POINT\"",
+        Shell::Bash.script()
+    );
+    let path = format!("{}:{}", dir.path().display(), std::env::var("PATH").unwrap());
+    let output = std::process::Command::new("bash")
+        .args(["-c", &script])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "sudo git-status --help|15"
+    );
+}
+
+#[test]
+fn test_bento_command_description() {
+    let cmd = BentoCommand::new("ls", Category::Bin).with_description("list directory contents");
+    assert_eq!(cmd.description.as_deref(), Some("list directory contents"));
+
+    // Descriptions are left out of JSON when missing
+    let json = serde_json::to_string(&cmd).unwrap();
+    assert_eq!(serde_json::from_str::<BentoCommand>(&json).unwrap(), cmd);
+    let bare = serde_json::to_string(&BentoCommand::new("ls", Category::Bin)).unwrap();
+    assert_eq!(bare, r#"{"name":"ls","category":"bin"}"#);
+
+    let rows = [Row { command: &cmd, score: 3 }];
+    let out = write_to_string(Format::Csv, &rows);
+    assert_eq!(out, "name,category,also_in,description,definition,path,link_target,also_at,provides,packages,score\nls,bin,,list directory contents,,,,,,,3\n");
+}
+
+#[test]
+fn test_bin_descriptions_from_man_index() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let apropos = bin.path().join("apropos");
+    fs::write(
+        &apropos,
+        "#!/bin/sh\n\
+         echo 'mytool (1)           - does my things'\n\
+         echo 'mytool (3)           - library call, not a command'\n\
+         echo 'othertool(8), mytool-extra(1) - does other things'\n",
+    )
+    .unwrap();
+    fs::set_permissions(&apropos, fs::Permissions::from_mode(0o755)).unwrap();
+    for name in ["mytool", "othertool", "undocumented"] {
+        let path = bin.path().join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let output = bento(cache.path())
+        .args(["--format", "ndjson", "--bin"])
+        .env("PATH", bin.path())
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let commands: Vec<BentoCommand> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
         .collect();
-    
-    assert_eq!(homebrew_only.len(), 1);
-    assert_eq!(homebrew_only[0].name, "brew");
+    let description = |name: &str| {
+        commands
+            .iter()
+            .find(|cmd| cmd.name == name)
+            .and_then(|cmd| cmd.description.clone())
+    };
+    assert_eq!(description("mytool").as_deref(), Some("does my things"));
+    assert_eq!(description("othertool").as_deref(), Some("does other things"));
+    assert_eq!(description("undocumented"), None);
+}
+
+#[test]
+fn test_match_command_descriptions() {
+    let jq = BentoCommand::new("jq", Category::Bin)
+        .with_description("Command-line JSON processor");
+    let json_pp = BentoCommand::new("json_pp", Category::Bin)
+        .with_description("JSON serializer and pretty printer");
+
+    // Descriptions are only searched when asked to
+    assert_eq!(match_command("json", &jq, false), None);
+    let (field, m) = match_command("json", &jq, true).unwrap();
+    assert_eq!(field, Field::Description);
+    assert!(m.score > 0 && m.positions.is_empty());
+
+    // A matching name wins over the description
+    let (field, m) = match_command("json", &json_pp, true).unwrap();
+    assert_eq!(field, Field::Name);
+    assert_eq!(m.positions, vec![0, 1, 2, 3]);
+
+    // Every word has to appear, in any order and case
+    assert!(match_command("PRETTY json", &json_pp, true).is_some());
+    assert!(match_command("json pretty", &jq, true).is_none());
+    assert!(match_command("  ", &jq, true).is_none());
+}
+
+#[test]
+fn test_cli_describe_ranks_name_matches_first() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let apropos = bin.path().join("apropos");
+    fs::write(
+        &apropos,
+        "#!/bin/sh\n\
+         echo 'jq (1) - Command-line JSON processor'\n\
+         echo 'prettyjson (1) - reformat documents'\n",
+    )
+    .unwrap();
+    fs::set_permissions(&apropos, fs::Permissions::from_mode(0o755)).unwrap();
+    for name in ["jq", "prettyjson"] {
+        let path = bin.path().join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let names = |args: &[&str]| {
+        let output = bento(cache.path())
+            .args(["--format", "tsv", "--bin"])
+            .args(args)
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split('\t').next().map(String::from))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&["json"]), ["prettyjson"]);
+    assert_eq!(names(&["-d", "json"]), ["prettyjson", "jq"]);
+    assert_eq!(names(&["--describe", "json processor"]), ["jq"]);
+}
+
+#[test]
+fn test_bin_paths_and_link_targets() {
+    let bin = tempfile::tempdir().unwrap();
+    let other = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let tool = other.path().join("tool-1.2");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    std::os::unix::fs::symlink(&tool, bin.path().join("tool")).unwrap();
+    std::os::unix::fs::symlink(other.path().join("missing"), bin.path().join("dangling")).unwrap();
+    fs::copy(&tool, bin.path().join("plain")).unwrap();
+
+    let run = |args: &[&str]| {
+        let output = bento(cache.path())
+            .args(args)
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let commands: Vec<BentoCommand> = run(&["--format", "ndjson", "--bin"])
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let find = |name: &str| commands.iter().find(|cmd| cmd.name == name);
+    let tool_target = fs::canonicalize(&tool).unwrap();
+
+    // Symlinked executables are found, and resolved to what they point at
+    let linked = find("tool").unwrap();
+    assert_eq!(linked.path, Some(bin.path().join("tool")));
+    assert_eq!(linked.link_target.as_ref(), Some(&tool_target));
+    let plain = find("plain").unwrap();
+    assert_eq!(plain.path, Some(bin.path().join("plain")));
+    assert_eq!(plain.link_target, None);
+    assert!(find("dangling").is_none());
+
+    let long = run(&["--long", "--bin", "tool"]);
+    assert!(long.contains(&format!(
+        "{} → {}",
+        bin.path().join("tool").display(),
+        tool_target.display()
+    )));
+    assert!(!run(&["--bin", "tool"]).contains(&bin.path().display().to_string()));
+}
+
+#[test]
+fn test_resolve_order() {
+    let commands = [
+        BentoCommand::new("ls", Category::Bin).with_path("/usr/local/bin/ls", None),
+        BentoCommand::new("ls", Category::Homebrew),
+        BentoCommand::new("ls", Category::Bin).with_path("/usr/bin/ls", None),
+        BentoCommand::new("ls", Category::Alias),
+        BentoCommand::new("ls", Category::Function),
+        BentoCommand::new("git", Category::Bin).with_path("/usr/bin/git", None),
+        BentoCommand::new("git", Category::Bin).with_path("/usr/bin/git", None),
+    ];
+
+    // Aliases, then functions, then PATH order; packages are not run by name
+    let order: Vec<_> = resolve::resolve(&commands, "ls")
+        .into_iter()
+        .map(|cmd| (cmd.category.clone(), cmd.path.clone()))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            (Category::Alias, None),
+            (Category::Function, None),
+            (Category::Bin, Some(PathBuf::from("/usr/local/bin/ls"))),
+            (Category::Bin, Some(PathBuf::from("/usr/bin/ls"))),
+        ]
+    );
+
+    // The same directory listed twice in PATH is not a shadow
+    let shadows = resolve::shadows(&commands);
+    assert_eq!(shadows.len(), 1);
+    assert_eq!(shadows[0].name, "ls");
+    assert_eq!(shadows[0].winner().category, Category::Alias);
+}
+
+#[test]
+fn test_cli_shadows() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    for dir in [&first, &second] {
+        for name in ["tool", &format!("only-{}", dir.path().display()).replace('/', "-")] {
+            let path = dir.path().join(name);
+            fs::write(&path, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+    let path = format!("{}:{}", first.path().display(), second.path().display());
+
+    let output = bento(cache.path())
+        .arg("shadows")
+        .env("PATH", path)
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{stdout}");
+    assert_eq!(lines[0], "tool");
+    assert_eq!(
+        lines[1].trim_end(),
+        format!("  ✓ bin      {}", first.path().join("tool").display())
+    );
+    assert_eq!(
+        lines[2].trim_end(),
+        format!("    bin      {}", second.path().join("tool").display())
+    );
+}
+
+#[test]
+fn test_resolve_owners() {
+    let cargo_root = tempfile::tempdir().unwrap();
+    fs::write(cargo_root.path().join(".crates.toml"), "").unwrap();
+    let rg = cargo_root.path().join("bin").join("rg");
+
+    let commands = [
+        BentoCommand::new("git", Category::Homebrew),
+        BentoCommand::new("git-lfs", Category::Homebrew),
+        BentoCommand::new("@vue/cli", Category::Npm),
+        BentoCommand::new("ripgrep", Category::Cargo).with_provides(vec!["rg".to_string()]),
+        BentoCommand::new("black", Category::Pip).with_provides(vec!["black".to_string()]),
+    ];
+    let owner_names = |cmd: &BentoCommand| {
+        resolve::owners(&commands, cmd)
+            .into_iter()
+            .map(|owner| owner.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let git = BentoCommand::new("git", Category::Bin).with_path(
+        "/usr/local/bin/git",
+        Some(PathBuf::from("/usr/local/Cellar/git/2.45.0/bin/git")),
+    );
+    assert_eq!(owner_names(&git), ["git"]);
+
+    let vue = BentoCommand::new("vue", Category::Bin).with_path(
+        "/usr/local/bin/vue",
+        Some(PathBuf::from("/usr/local/lib/node_modules/@vue/cli/bin/vue.js")),
+    );
+    assert_eq!(owner_names(&vue), ["@vue/cli"]);
+
+    let rg = BentoCommand::new("rg", Category::Bin).with_path(rg, None);
+    assert_eq!(owner_names(&rg), ["ripgrep"]);
+
+    // Only executables in a Python environment's bin directory belong to pip
+    let venv = tempfile::tempdir().unwrap();
+    let black = BentoCommand::new("black", Category::Bin).with_path(venv.path().join("black"), None);
+    assert!(owner_names(&black).is_empty());
+    fs::write(venv.path().join("python3"), "").unwrap();
+    assert_eq!(owner_names(&black), ["black"]);
+    assert!(resolve::owners(&commands, &BentoCommand::new("git", Category::Alias)).is_empty());
+}
+
+#[test]
+fn test_cli_which() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    for dir in [&first, &second] {
+        let path = dir.path().join("tool");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!("{}:{}", first.path().display(), second.path().display());
+    let which = |name: &str| {
+        bento(cache.path())
+            .args(["which", name])
+            .env("PATH", &path)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .env("NO_COLOR", "1")
+            .assert()
+    };
+
+    which("tool").success().stdout(format!(
+        "  ✓ bin      {}\n    bin      {}\n",
+        first.path().join("tool").display(),
+        second.path().join("tool").display()
+    ));
+    which("no-such-tool")
+        .failure()
+        .code(1)
+        .stderr(predicates::str::contains("no-such-tool not found"));
+}
+
+#[test]
+fn test_cli_ignores_closed_stdout() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    for dir in [&first, &second] {
+        let path = dir.path().join("tool");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let path = format!("{}:{}", first.path().display(), second.path().display());
+    for args in [&["shadows"][..], &["which", "tool"]] {
+        // Like `bento shadows | head` once head has quit
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("bento"))
+            .args(args)
+            .env("PATH", &path)
+            .env("HOME", cache.path())
+            .env("BENTO_CONFIG", cache.path().join("config.toml"))
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{args:?}: {stderr}");
+        assert!(stderr.is_empty(), "{args:?}: {stderr}");
+    }
+}
+
+#[test]
+fn test_bento_command_expansion() {
+    let alias = BentoCommand::new("ll", Category::Alias).with_definition("ls -lah");
+    assert_eq!(alias.expansion(), Some("ls -lah"));
+    let function = BentoCommand::new("greet", Category::Function)
+        .with_definition("greet () \n{ \n    echo hi\n}");
+    assert_eq!(function.expansion(), None);
+    assert_eq!(BentoCommand::new("ls", Category::Bin).expansion(), None);
+}
+
+#[test]
+fn test_function_bodies() {
+    let cache = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    fs::write(
+        home.path().join(".bashrc"),
+        "greet() {\n    echo \"hello $1\"\n}\n_private() { :; }\n",
+    )
+    .unwrap();
+    let output = bento(home.path())
+        .args(["--format", "ndjson", "--function"])
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .output()
+        .unwrap();
+    let commands: Vec<BentoCommand> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let greet = commands.iter().find(|cmd| cmd.name == "greet").unwrap();
+    let definition = greet.definition.as_deref().unwrap();
+    assert!(definition.starts_with("greet()"), "{definition}");
+    assert!(definition.contains("echo \"hello $1\""), "{definition}");
+    assert!(definition.ends_with('}'), "{definition}");
+    assert!(commands.iter().all(|cmd| cmd.name != "_private"));
+}
+
+#[test]
+fn test_merge_collapses_duplicates() {
+    let commands = vec![
+        BentoCommand::new("bat", Category::Cargo).with_provides(vec!["bat".to_string()]),
+        BentoCommand::new("ll", Category::Alias).with_definition("ls -lah"),
+        BentoCommand::new("bat", Category::Bin)
+            .with_path("/home/me/.cargo/bin/bat", None)
+            .with_description("a cat clone with wings"),
+        BentoCommand::new("ll", Category::Alias).with_definition("ls -lah"),
+        BentoCommand::new("bat", Category::Bin).with_path("/usr/bin/bat", None),
+        BentoCommand::new("bat", Category::Homebrew),
+    ];
+    let merged = bento::merge(commands);
+    assert_eq!(merged.len(), 2);
+
+    // The executable that runs is the entry; the package categories come along
+    let bat = &merged[0];
+    assert_eq!(bat.name, "bat");
+    assert_eq!(bat.category, Category::Bin);
+    assert_eq!(bat.also_in, [Category::Cargo, Category::Homebrew]);
+    assert_eq!(bat.path, Some(PathBuf::from("/home/me/.cargo/bin/bat")));
+    assert_eq!(bat.description.as_deref(), Some("a cat clone with wings"));
+    assert_eq!(bat.provides, ["bat"]);
+    assert!(bat.is_in(&Category::Homebrew));
+    assert!(!bat.is_in(&Category::Pip));
+
+    let ll = &merged[1];
+    assert_eq!(ll.category, Category::Alias);
+    assert!(ll.also_in.is_empty());
+    assert_eq!(ll.expansion(), Some("ls -lah"));
+}
+
+#[test]
+fn test_merge_links_packages_to_their_executables() {
+    let commands = vec![
+        BentoCommand::new("ripgrep", Category::Cargo).with_provides(vec!["rg".to_string()]),
+        BentoCommand::new("typescript", Category::Npm)
+            .with_provides(vec!["tsc".to_string(), "tsserver".to_string()]),
+        BentoCommand::new("fd-find", Category::Cargo).with_provides(vec!["fd".to_string()]),
+        BentoCommand::new("rg", Category::Bin).with_path("/home/me/.cargo/bin/rg", None),
+        BentoCommand::new("tsc", Category::Bin).with_path("/usr/local/bin/tsc", None),
+    ];
+    let merged = bento::merge(commands);
+    // Packages installing several executables, or none that was found, stay
+    assert_eq!(names(&merged), ["typescript", "fd-find", "rg", "tsc"]);
+
+    let rg = &merged[2];
+    assert_eq!(rg.category, Category::Bin);
+    assert_eq!(rg.also_in, [Category::Cargo]);
+    assert_eq!(rg.packages, ["ripgrep"]);
+    assert_eq!(rg.path, Some(PathBuf::from("/home/me/.cargo/bin/rg")));
+
+    // The package name still finds the entry
+    let found = Searcher::new(&merged).search(&SearchQuery::new("ripgrep"));
+    assert_eq!(found.results.len(), 1);
+    assert_eq!(found.results[0].command.name, "rg");
+}
+
+#[test]
+fn test_cli_raw_keeps_duplicates() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    for dir in [&first, &cache] {
+        let path = dir.path().join("tool");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let target = fs::canonicalize(cache.path().join("tool")).unwrap();
+    std::os::unix::fs::symlink(&target, second.path().join("tool")).unwrap();
+    let path = format!("{}:{}", first.path().display(), second.path().display());
+    let rows = |args: &[&str]| {
+        let output = bento(cache.path())
+            .args(["--format", "ndjson", "--bin"])
+            .args(args)
+            .env("PATH", &path)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<BentoCommand>(line).unwrap())
+            .filter(|cmd| cmd.name == "tool")
+            .collect::<Vec<_>>()
+    };
+
+    let merged = rows(&[]);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].path, Some(first.path().join("tool")));
+    // Later places keep what they link to
+    let also_at = Location {
+        path: second.path().join("tool"),
+        link_target: Some(target.clone()),
+    };
+    assert_eq!(merged[0].also_at, [also_at]);
+    assert_eq!(rows(&["--raw"]).len(), 2);
+
+    // --long lists every place a merged executable was found
+    let output = bento(cache.path())
+        .args(["--long", "--bin", "tool"])
+        .env("PATH", &path)
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("    {}\n", first.path().join("tool").display())), "{stdout}");
+    let linked = format!("    {} → {}\n", second.path().join("tool").display(), target.display());
+    assert!(stdout.contains(&linked), "{stdout}");
+}
+
+#[test]
+fn test_cli_filters_apply_before_merging() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let tool = bin.path().join("foo");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(home.path().join(".bashrc"), "alias foo='ls -la'\n").unwrap();
+    let found = |args: &[&str]| {
+        let output = bento(home.path())
+            .args(["--format", "ndjson"])
+            .args(args)
+            .arg("foo")
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/bin/bash")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| serde_json::from_str::<BentoCommand>(line).unwrap())
+            .find(|cmd| cmd.name == "foo")
+            .unwrap()
+    };
+
+    let merged = found(&[]);
+    assert_eq!(merged.category, Category::Alias);
+    assert_eq!(merged.also_in, [Category::Bin]);
+
+    for args in [&["--bin"][..], &["-c", "bin"], &["-x", "alias"]] {
+        let foo = found(args);
+        assert_eq!(foo.category, Category::Bin, "{args:?}");
+        assert!(foo.also_in.is_empty(), "{args:?}");
+        assert_eq!(foo.definition, None, "{args:?}");
+        assert_eq!(foo.path, Some(tool.clone()), "{args:?}");
+    }
+}
+
+#[test]
+fn test_category_filter() {
+    let git = BentoCommand::new("git", Category::Bin);
+    let ll = BentoCommand::new("ll", Category::Alias);
+    let deploy = BentoCommand::new("deploy", Category::Other("plugin".to_string()));
+    let mut npm = BentoCommand::new("npm", Category::Bin);
+    npm.also_in.push(Category::Npm);
+
+    assert!([&git, &ll, &deploy, &npm].iter().all(|cmd| CategoryFilter::new().matches(cmd)));
+
+    // Included categories are a union
+    let filter = CategoryFilter {
+        include: vec![Category::Alias, Category::Other("plugin".to_string())],
+        exclude: Vec::new(),
+    };
+    assert!(filter.matches(&ll) && filter.matches(&deploy));
+    assert!(!filter.matches(&git));
+
+    // Excluding a category keeps entries also found elsewhere
+    let filter = CategoryFilter {
+        include: Vec::new(),
+        exclude: vec![Category::Bin],
+    };
+    assert!(!filter.matches(&git));
+    assert!(filter.matches(&ll) && filter.matches(&npm));
+}
+
+#[test]
+fn test_picker_set_filter() {
+    let commands = [
+        BentoCommand::new("git", Category::Bin),
+        BentoCommand::new("gs", Category::Alias),
+        BentoCommand::new("gco", Category::Function),
+    ];
+    let mut picker = Picker::new(&commands, "g");
+    picker.set_filter(&CategoryFilter {
+        include: Vec::new(),
+        exclude: vec![Category::Bin],
+    });
+    let names: Vec<_> = picker.results().iter().map(|result| result.command.name.as_str()).collect();
+    assert_eq!(names, ["gs", "gco"]);
+}
+
+#[test]
+fn test_cli_combined_filters() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let tool = bin.path().join("greeter");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    let home = tempfile::tempdir().unwrap();
+    fs::write(home.path().join(".bashrc"), "greet() { echo hi; }\n").unwrap();
+    let path = format!("{}:{}", bin.path().display(), std::env::var("PATH").unwrap());
+
+    let categories = |args: &[&str]| {
+        let output = bento(home.path())
+            .args(["--format", "tsv"])
+            .args(args)
+            .arg("greet")
+            .env("PATH", &path)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .output()
+            .unwrap();
+        let mut found: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .filter(|line| line.starts_with("greet"))
+            .filter_map(|line| line.split('\t').nth(1).map(String::from))
+            .collect();
+        found.sort();
+        found
+    };
+
+    assert_eq!(categories(&["--bin", "--function"]), ["bin", "function"]);
+    assert_eq!(categories(&["--category", "function,bin"]), ["bin", "function"]);
+    assert_eq!(categories(&["--bin", "-c", "function"]), ["bin", "function"]);
+    assert_eq!(categories(&["-x", "bin"]), ["function"]);
+    assert_eq!(categories(&["--bin", "--exclude", "bin"]), Vec::<String>::new());
+
+    bento(home.path())
+        .args(["--category", "bin,not a category"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_limit_and_all() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    for i in 1..=5 {
+        let path = bin.path().join(format!("tool{i}"));
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let run = |args: &[&str]| {
+        let output = bento(cache.path())
+            .arg("--bin")
+            .args(args)
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let limited = run(&["--limit", "2", "tool"]);
+    let lines: Vec<_> = limited.lines().collect();
+    assert_eq!(lines.len(), 3, "{limited}");
+    assert!(lines[2].contains("3 more matches"), "{limited}");
+    assert!(run(&["-n", "4", "tool"]).contains("1 more match "));
+
+    let all = run(&["--all", "tool"]);
+    assert_eq!(all.lines().count(), 5, "{all}");
+    assert!(!all.contains("more match"));
+
+    // Machine-readable output is limited too, but has no footer
+    assert_eq!(run(&["--format", "tsv", "-n", "2", "tool"]).lines().count(), 3);
+    assert_eq!(run(&["--format", "tsv", "-n", "1"]).lines().count(), 2);
+    assert_eq!(run(&["--format", "tsv"]).lines().count(), 6);
+
+    bento(cache.path())
+        .args(["--limit", "2", "--all", "tool"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_config_parse() {
+    let config: Config = r##"
+        disable = ["go"]
+        categories = ["bin", "Alias"]
+        exclude = ["cask"]
+        limit = 40
+        ignore = ["_*", "*.dll", "py?"]
+        timeout = 2.5
+
+        [timeouts]
+        pip = 1
+
+        [colors]
+        alias = "bright_yellow"
+        bin = "#ff8000"
+    "##
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.limit, Some(40));
+    assert_eq!(config.filter().include, vec![Category::Bin, Category::Alias]);
+    assert_eq!(config.filter().exclude, vec![Category::Cask]);
+
+    let palette = config.palette();
+    assert_eq!(palette.color(&Category::Alias), colored::Color::BrightYellow);
+    assert_eq!(palette.color(&Category::Bin), colored::Color::TrueColor { r: 255, g: 128, b: 0 });
+    assert_eq!(palette.color(&Category::Pip), Category::Pip.color());
+
+    assert!(config.is_ignored("_private"));
+    assert!(config.is_ignored("helper.dll"));
+    assert!(config.is_ignored("pyz"));
+    assert!(!config.is_ignored("py"));
+    assert!(!config.is_ignored("python"));
+
+    assert_eq!("".parse::<Config>().unwrap(), Config::default());
+    assert_eq!(Palette::new().color(&Category::Go), Category::Go.color());
+}
+
+#[test]
+fn test_config_rejects_invalid_settings() {
+    for text in [
+        "limti = 3",
+        "limit = \"many\"",
+        "timeout = -1",
+        "[timeouts]\npip = nan",
+        "timeout = 1e30",
+        "[timeouts]\npip = inf",
+        "[colors]\nbin = \"mauve\"",
+        "[colors]\n\"not a category\" = \"red\"",
+        "categories = [\"\"]",
+        "exclude = [\"bin, alias\"]",
+    ] {
+        assert!(text.parse::<Config>().is_err(), "accepted {text:?}");
+    }
+}
+
+#[test]
+fn test_config_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    assert_eq!(Config::load(&path).unwrap(), Config::default());
+
+    fs::write(&path, "limit = 5\n").unwrap();
+    assert_eq!(Config::load(&path).unwrap().limit, Some(5));
+
+    fs::write(&path, "limit = [\n").unwrap();
+    assert!(Config::load(&path).is_err());
+}
+
+#[test]
+fn test_config_configures_registry() {
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "a", available: true, names: vec!["a"] })
+        .register(FakeSource { name: "b", available: true, names: vec!["b"] })
+        .register(FakeSource { name: "c", available: true, names: vec!["c"] });
+    let config: Config = r#"
+        sources = ["a", "b", "z"]
+        disable = ["b"]
+        timeout = 3
+        [timeouts]
+        a = 0.5
+    "#
+    .parse()
+    .unwrap();
+
+    assert_eq!(config.configure(&mut registry), vec!["z".to_string()]);
+    let names: Vec<_> = registry.sources().map(|source| source.name().to_string()).collect();
+    assert_eq!(names, vec!["a"]);
+    assert_eq!(registry.timeout_for("a"), Duration::from_millis(500));
+    assert_eq!(registry.timeout_for("other"), Duration::from_secs(3));
+
+    // Sources are replaced in place, and only if registered
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "a", available: true, names: vec!["a"] })
+        .register(FakeSource { name: "b", available: true, names: vec!["b"] });
+    assert!(registry.replace(FakeSource { name: "a", available: true, names: vec!["new"] }));
+    assert!(!registry.replace(FakeSource { name: "z", available: true, names: vec!["z"] }));
+    let commands: Vec<_> = registry.collect().into_iter().map(|cmd| cmd.name).collect();
+    assert_eq!(commands, ["new", "b"]);
+}
+
+#[test]
+fn test_cli_config_accepts_huge_timeouts() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let config = cache.path().join("config.toml");
+    let tool = bin.path().join("tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(&config, "timeout = 1e19\n\n[timeouts]\npath = 1e19\n").unwrap();
+
+    bento(cache.path())
+        .arg("tool")
+        .env("PATH", bin.path())
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("BENTO_CONFIG", &config)
+        .env("SHELL", "/nonexistent")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("tool"));
+}
+
+#[test]
+fn test_cli_config() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let config_dir = tempfile::tempdir().unwrap();
+    let config = config_dir.path().join("config.toml");
+    for name in ["tool1", "tool2", "tool3", "tool-debug"] {
+        let path = bin.path().join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    fs::write(&config, "limit = 2\nignore = [\"*-debug\"]\ndisable = [\"function\"]\n").unwrap();
+    let run = |args: &[&str]| {
+        let output = bento(cache.path())
+            .args(args)
+            .env("PATH", bin.path())
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("BENTO_CONFIG", &config)
+            .env("SHELL", "/nonexistent")
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap();
+        (output.status.code(), String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let (_, path) = run(&["config", "path"]);
+    assert_eq!(path.trim(), config.to_str().unwrap());
+
+    // The configured limit applies unless overridden
+    let (_, text) = run(&["--bin", "tool"]);
+    assert_eq!(text.lines().count(), 3, "{text}");
+    assert!(text.contains("1 more match"), "{text}");
+    let (_, text) = run(&["--bin", "--all", "tool"]);
+    assert_eq!(text.lines().count(), 3, "{text}");
+    assert!(!text.contains("tool-debug"));
+
+    // Ignored names are hidden everywhere but `which`
+    let (_, tsv) = run(&["--format", "tsv", "--bin"]);
+    assert!(!tsv.contains("tool-debug"), "{tsv}");
+    let (code, which) = run(&["which", "tool-debug"]);
+    assert_eq!(code, Some(0));
+    assert!(which.contains("tool-debug"), "{which}");
+
+    let (_, shown) = run(&["config", "show"]);
+    let shown: Config = shown.parse().unwrap();
+    assert_eq!(shown.limit, Some(2));
+    assert_eq!(shown.disable, vec!["function".to_string()]);
+    assert_eq!(shown.timeout, Some(10.0));
+
+    fs::write(&config, "limit = \"two\"\n").unwrap();
+    let (code, _) = run(&["tool"]);
+    assert_eq!(code, Some(2));
+}
+
+#[test]
+fn test_custom_sources() {
+    let tools = tempfile::tempdir().unwrap();
+    for (name, mode) in [("deploy", 0o755), ("README", 0o644)] {
+        let path = tools.path().join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+    let tool = Category::Other("tool".to_string());
+    let plugin = Category::Other("plugin".to_string());
+    let mut registry = Registry::new();
+    registry
+        .register(CustomSource::dir("tools", tool.clone(), tools.path()))
+        .register(CustomSource::command(
+            "plugins",
+            plugin.clone(),
+            "printf 'login\\tLog in to the cluster\\n\\nlogs\\n'",
+        ));
+
+    let commands = registry.collect();
+    assert_eq!(commands.len(), 3, "{commands:?}");
+    assert_eq!(commands[0].name, "deploy");
+    assert_eq!(commands[0].category, tool);
+    assert_eq!(commands[0].path.as_deref(), Some(tools.path().join("deploy").as_path()));
+    assert_eq!(commands[1].name, "login");
+    assert_eq!(commands[1].category, plugin);
+    assert_eq!(commands[1].description.as_deref(), Some("Log in to the cluster"));
+    assert_eq!(commands[2].name, "logs");
+    assert_eq!(commands[2].description, None);
+}
+
+#[test]
+fn test_config_custom_sources() {
+    let config: Config = r#"
+        [[source]]
+        name = "tools"
+        dir = "~/tools"
+        color = "bright green"
+
+        [[source]]
+        name = "plugins"
+        command = "acme plugins"
+        category = "acme"
+    "#
+    .parse()
+    .unwrap();
+    assert_eq!(config.custom_sources.len(), 2);
+    assert_eq!(config.custom_sources[0].category(), Category::Other("tools".to_string()));
+    assert_eq!(config.custom_sources[1].category(), Category::Other("acme".to_string()));
+    assert_eq!(
+        config.palette().color(&Category::Other("tools".to_string())),
+        colored::Color::BrightGreen
+    );
+
+    let mut registry = Registry::new();
+    assert!(config.configure(&mut registry).is_empty());
+    let names: Vec<_> = registry.sources().map(|source| source.name().to_string()).collect();
+    assert_eq!(names, vec!["tools", "plugins"]);
+
+    for text in [
+        "[[source]]\nname = \"x\"",
+        "[[source]]\nname = \"x\"\ndir = \"/\"\ncommand = \"true\"",
+        "[[source]]\nname = \"pip\"\ncommand = \"true\"",
+        "[[source]]\nname = \"x\"\ncommand = \"true\"\n[[source]]\nname = \"x\"\ncommand = \"true\"",
+        "[[source]]\nname = \"x\"\ncommand = \"true\"\ncolor = \"mauve\"",
+        "[[source]]\nname = \"my tools\"\ncommand = \"true\"",
+    ] {
+        assert!(text.parse::<Config>().is_err(), "accepted {text:?}");
+    }
+}
+
+#[test]
+fn test_searcher_ranking_and_limit() {
+    let commands = [
+        BentoCommand::new("gitk", Category::Bin),
+        BentoCommand::new("legit", Category::Bin),
+        BentoCommand::new("git", Category::Bin),
+        BentoCommand::new("gti", Category::Alias),
+        BentoCommand::new("jq", Category::Bin).with_description("JSON processor for git logs"),
+    ];
+    let searcher = Searcher::new(&commands);
+    let names = |query: &SearchQuery| -> Vec<String> {
+        searcher
+            .search(query)
+            .results
+            .iter()
+            .map(|result| result.command.name.clone())
+            .collect()
+    };
+
+    assert_eq!(names(&SearchQuery::new("git")), ["git", "gitk", "legit"]);
+
+    // Description matches come after every name match
+    let describe = SearchQuery::new("git").with_mode(SearchMode::Descriptions);
+    assert_eq!(names(&describe), ["git", "gitk", "legit", "jq"]);
+    let results = searcher.search(&describe).results;
+    assert_eq!(results[0].field, Field::Name);
+    assert_eq!(results[3].field, Field::Description);
+
+    let limited = searcher.search(&SearchQuery::new("git").with_limit(Some(2)));
+    assert_eq!(limited.results.len(), 2);
+    assert_eq!(limited.total, 3);
+    assert_eq!(limited.omitted(), 1);
+
+    let filtered = SearchQuery::new("g").with_filter(CategoryFilter {
+        include: Vec::new(),
+        exclude: vec![Category::Bin],
+    });
+    assert_eq!(names(&filtered), ["gti"]);
+    assert_eq!(searcher.search(&SearchQuery::new("zzz")), Default::default());
+}
+
+/// Collect `source` with commands answered by `runner`.
+fn collect_with(source: &dyn Source, runner: FakeRunner) -> Vec<BentoCommand> {
+    source.collect(&Context::with_runner(Duration::from_secs(5), Arc::new(runner)))
+}
+
+fn names(commands: &[BentoCommand]) -> Vec<&str> {
+    commands.iter().map(|cmd| cmd.name.as_str()).collect()
+}
+
+#[test]
+fn test_fake_runner() {
+    let failure = Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr: b"Error: no formulae installed\n".to_vec(),
+    };
+    let runner = Arc::new(
+        FakeRunner::new()
+            .with_stdout(&["echo", "hello"], "hello\n")
+            .with_output(&["brew", "list"], failure),
+    );
+    let ctx = Context::with_runner(Duration::from_secs(5), runner);
+
+    assert_eq!(ctx.stdout("echo", &["hello"]).as_deref(), Some("hello\n"));
+    let output = ctx.output("brew", &["list"]).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stderr, b"Error: no formulae installed\n");
+    // Anything not recorded fails like a missing program
+    assert_eq!(ctx.output("echo", &["bye"]).unwrap_err().kind(), ErrorKind::NotFound);
+    assert!(!ctx.timed_out());
+}
+
+#[test]
+fn test_registry_runs_sources_with_its_runner() {
+    let mut registry = Registry::new();
+    registry
+        .register(CustomSource::command("plugins", Category::Other("acme".to_string()), "acme plugins"))
+        .set_runner(FakeRunner::new().with_stdout(&["sh", "-c", "acme plugins"], "deploy\tShip it\n"));
+
+    let commands = registry.collect();
+    assert_eq!(names(&commands), ["deploy"]);
+    assert_eq!(commands[0].description.as_deref(), Some("Ship it"));
+}
+
+#[test]
+fn test_registry_asks_its_runner_which_tools_are_installed() {
+    let mut registry = Registry::new();
+    registry.register(YarnSource).register(NpmSource).set_runner(
+        FakeRunner::new()
+            .with_stdout(&["yarn", "global", "list"], "info \"typescript@5.4.5\" has binaries:\n   - tsc\n"),
+    );
+
+    let collection = registry.collect_report();
+    assert_eq!(names(&collection.commands), ["typescript"]);
+    let status = |name: &str| {
+        collection.reports.iter().find(|report| report.name == name).map(|report| report.status.clone())
+    };
+    assert_eq!(status("yarn"), Some(SourceStatus::Ok(1)));
+    // Without recorded commands, npm counts as missing even where it is on PATH
+    assert_eq!(status("npm"), Some(SourceStatus::NotInstalled));
+}
+
+#[test]
+fn test_npm_fixture() {
+    let runner = FakeRunner::new().with_stdout(
+        &["npm", "list", "-g", "--depth=0", "--parseable"],
+        include_str!("fixtures/npm_list_parseable.txt"),
+    );
+    let commands = collect_with(&NpmSource, runner);
+    // Scoped packages keep their scope, and the prefix itself is skipped
+    assert_eq!(names(&commands), ["@angular/cli", "@vue/cli", "npm", "typescript"]);
+    assert!(commands.iter().all(|cmd| cmd.category == Category::Npm));
+}
+
+#[test]
+fn test_npm_scoped_package_manifest() {
+    let prefix = tempfile::tempdir().unwrap();
+    let modules = prefix.path().join("lib").join("node_modules");
+    for (dir, manifest) in [
+        ("@vue/cli", r#"{"name": "@vue/cli", "description": "Command line interface for Vue.js", "bin": {"vue": "bin/vue.js"}}"#),
+        ("@scope/single", r#"{"name": "@scope/single", "bin": "index.js"}"#),
+    ] {
+        fs::create_dir_all(modules.join(dir)).unwrap();
+        fs::write(modules.join(dir).join("package.json"), manifest).unwrap();
+    }
+    let stdout = format!(
+        "{}\n{}\n{}\n",
+        prefix.path().join("lib").display(),
+        modules.join("@vue/cli").display(),
+        modules.join("@scope/single").display(),
+    );
+    let runner = FakeRunner::new().with_stdout(&["npm", "list", "-g", "--depth=0", "--parseable"], stdout);
+
+    let commands = collect_with(&NpmSource, runner);
+    assert_eq!(names(&commands), ["@vue/cli", "@scope/single"]);
+    assert_eq!(commands[0].description.as_deref(), Some("Command line interface for Vue.js"));
+    assert_eq!(commands[0].provides, ["vue"]);
+    assert_eq!(commands[1].provides, ["single"]);
+}
+
+#[test]
+fn test_yarn_fixture() {
+    let runner = FakeRunner::new()
+        .with_stdout(&["yarn", "global", "list"], include_str!("fixtures/yarn_global_list.txt"));
+    let commands = collect_with(&YarnSource, runner);
+
+    assert_eq!(names(&commands), ["create-react-app", "@vue/cli", "typescript"]);
+    assert_eq!(commands[1].provides, ["vue"]);
+    assert_eq!(commands[2].provides, ["tsc", "tsserver"]);
+}
+
+#[test]
+fn test_cargo_fixture() {
+    let runner = FakeRunner::new()
+        .with_stdout(&["cargo", "install", "--list"], include_str!("fixtures/cargo_install_list.txt"));
+    let commands = collect_with(&CargoSource, runner);
+
+    assert_eq!(names(&commands), ["bat", "cargo-edit", "mytool", "ripgrep"]);
+    assert_eq!(commands[0].provides, ["bat"]);
+    assert_eq!(
+        commands[1].provides,
+        ["cargo-add", "cargo-rm", "cargo-set-version", "cargo-upgrade"]
+    );
+    // Packages installed from a path or git still parse
+    assert_eq!(commands[2].provides, ["mytool"]);
+    assert_eq!(commands[3].provides, ["rg"]);
+}
+
+#[test]
+fn test_homebrew_fixtures() {
+    let runner = || {
+        FakeRunner::new()
+            .with_stdout(&["brew", "list", "--formula"], include_str!("fixtures/brew_list_formula.txt"))
+            .with_stdout(&["brew", "list", "--cask"], include_str!("fixtures/brew_list_cask.txt"))
+            .with_stdout(&["brew", "info", "--json=v2", "--installed"], include_str!("fixtures/brew_info.json"))
+    };
+
+    let formulae = collect_with(&HomebrewSource, runner());
+    assert_eq!(names(&formulae), ["bat", "git", "python@3.12"]);
+    assert_eq!(formulae[1].description.as_deref(), Some("Distributed revision control system"));
+    assert!(formulae.iter().all(|cmd| cmd.category == Category::Homebrew));
+
+    let casks = collect_with(&CaskSource, runner());
+    assert_eq!(names(&casks), ["firefox", "visual-studio-code"]);
+    assert_eq!(casks[0].description.as_deref(), Some("Web browser"));
+    assert_eq!(casks[1].description, None);
+}
+
+#[test]
+fn test_pip_fixture() {
+    let site_packages = tempfile::tempdir().unwrap();
+    let dist_info = site_packages.path().join("black-24.4.2.dist-info");
+    fs::create_dir_all(&dist_info).unwrap();
+    fs::write(
+        dist_info.join("METADATA"),
+        "Metadata-Version: 2.3\nName: black\nSummary: The uncompromising code formatter.\n\nLong description\n",
+    )
+    .unwrap();
+    fs::write(
+        dist_info.join("RECORD"),
+        "../../../bin/black,sha256=abc,1234\n../../../bin/blackd,sha256=def,1234\nblack/__init__.py,,\n",
+    )
+    .unwrap();
+    let version = format!(
+        "pip 24.0 from {}/pip (python 3.12)\n",
+        site_packages.path().display()
+    );
+    let runner = FakeRunner::new()
+        .with_stdout(&["pip", "list", "--format=freeze"], include_str!("fixtures/pip_list_freeze.txt"))
+        .with_stdout(&["pip", "--version"], version);
+
+    let commands = collect_with(&PipSource, runner);
+    assert_eq!(names(&commands), ["black", "Django", "zope.interface"]);
+    assert_eq!(commands[0].description.as_deref(), Some("The uncompromising code formatter."));
+    assert_eq!(commands[0].provides, ["black", "blackd"]);
+}
+
+#[test]
+fn test_go_fixture() {
+    let runner = FakeRunner::new()
+        .with_stdout(&["go", "list", "-m", "all"], include_str!("fixtures/go_list_m_all.txt"));
+    // The main module, listed first without a version, is not a dependency
+    assert_eq!(names(&collect_with(&GoSource, runner)), ["cobra", "sys"]);
+
+    // Outside a module `go list` fails, which is not a failure of the source
+    let outside = Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr: b"go: cannot match \"all\": go.mod file not found in current directory or any parent directory\n".to_vec(),
+    };
+    let ctx = Context::with_runner(
+        Duration::from_secs(5),
+        Arc::new(FakeRunner::new().with_output(&["go", "list", "-m", "all"], outside)),
+    );
+    assert!(GoSource.collect(&ctx).is_empty());
+    assert_eq!(ctx.failure(), None);
+}
+
+#[test]
+fn test_shell_fixtures() {
+    let home = tempfile::tempdir().unwrap();
+    let runner = FakeRunner::new()
+        .with_stdout(&["/bin/bash", "-i", "-c", "alias"], include_str!("fixtures/alias.txt"))
+        .with_stdout(&["/bin/bash", "-i", "-c", "declare -f"], include_str!("fixtures/declare_f.txt"));
+
+    let aliases = AliasSource::new().with_home(home.path()).with_shell("/bin/bash");
+    // The shell is only run when asked to
+    assert!(collect_with(&aliases, runner.clone()).is_empty());
+    let aliases = collect_with(&aliases.with_interactive_shell(true), runner.clone());
+    assert_eq!(names(&aliases), ["gs", "ll", "quote"]);
+    assert_eq!(aliases[0].definition.as_deref(), Some("git status"));
+    assert_eq!(aliases[2].definition.as_deref(), Some("echo 'hi'"));
+
+    let functions = FunctionSource::new()
+        .with_home(home.path())
+        .with_shell("/bin/bash")
+        .with_interactive_shell(true);
+    let functions = collect_with(&functions, runner);
+    assert_eq!(names(&functions), ["mkcd"]);
+    assert!(functions[0].definition.as_deref().unwrap().contains("mkdir -p"));
+
+    // A shell that fails is reported, not taken for one without aliases
+    let failed = Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr: b"zsh: parse error\n".to_vec(),
+    };
+    let mut registry = Registry::new();
+    registry
+        .register(
+            AliasSource::new()
+                .with_home(home.path())
+                .with_shell("/bin/zsh")
+                .with_interactive_shell(true),
+        )
+        .set_runner(FakeRunner::new().with_output(&["/bin/zsh", "-i", "-c", "alias"], failed));
+    let reports = registry.collect_report().reports;
+    assert_eq!(
+        reports[0].status,
+        SourceStatus::Failed { code: Some(1), stderr: "zsh: parse error".to_string() }
+    );
+    let mut registry = Registry::new();
+    registry.register(
+        AliasSource::new()
+            .with_home(home.path())
+            .with_shell("/nonexistent")
+            .with_interactive_shell(true),
+    );
+    let reports = registry.collect_report().reports;
+    assert!(matches!(reports[0].status, SourceStatus::Failed { code: None, .. }), "{:?}", reports[0]);
+}
+
+#[test]
+fn test_registry_reports_source_status() {
+    let other = || Category::Other("custom".to_string());
+    let mut registry = Registry::new();
+    registry
+        .register(FakeSource { name: "fine", available: true, names: vec!["a", "b"] })
+        .register(FakeSource { name: "missing", available: false, names: vec!["c"] })
+        .register(CustomSource::command("broken", other(), "echo partial; echo boom >&2; exit 3"))
+        .register(CustomSource::dir("nodir", other(), "/nonexistent/bento-tools"))
+        .register(SleepySource)
+        .set_source_timeout("sleepy", Duration::from_millis(100));
+
+    let collection = registry.collect_report();
+    let statuses: Vec<_> = collection
+        .reports
+        .iter()
+        .map(|report| (report.name.as_str(), report.status.clone()))
+        .collect();
+    assert_eq!(statuses[0], ("fine", SourceStatus::Ok(2)));
+    assert_eq!(statuses[1], ("missing", SourceStatus::NotInstalled));
+    assert_eq!(
+        statuses[2],
+        ("broken", SourceStatus::Failed { code: Some(3), stderr: "boom".to_string() })
+    );
+    assert!(matches!(&statuses[3].1, SourceStatus::Failed { code: None, stderr } if stderr.contains("/nonexistent")));
+    assert_eq!(statuses[4], ("sleepy", SourceStatus::TimedOut));
+    assert_eq!(collection.timed_out, vec!["sleepy".to_string()]);
+    assert!(collection.reports.iter().all(|report| !report.cached));
+
+    // What a failing source listed before it failed is kept
+    let names: Vec<_> = collection.commands.iter().map(|cmd| cmd.name.as_str()).collect();
+    assert_eq!(names, ["a", "b", "partial"]);
+}
+
+#[test]
+fn test_source_status_display() {
+    assert_eq!(SourceStatus::Ok(1).to_string(), "1 command");
+    assert_eq!(SourceStatus::Ok(12).to_string(), "12 commands");
+    assert_eq!(SourceStatus::NotInstalled.to_string(), "not installed");
+    assert_eq!(SourceStatus::TimedOut.to_string(), "timed out");
+    let failed = SourceStatus::Failed {
+        code: Some(1),
+        stderr: "\nERROR: pip is broken\nmore detail\n".to_string(),
+    };
+    assert_eq!(failed.to_string(), "failed with exit code 1: ERROR: pip is broken");
+}
+
+#[test]
+fn test_collect_cached_retries_failed_sources() {
+    let failure = Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr: b"offline\n".to_vec(),
+    };
+    let mut registry = Registry::new();
+    registry
+        .register(CustomSource::command("flaky", Category::Other("x".to_string()), "flaky"))
+        .register(CustomSource::command("steady", Category::Other("x".to_string()), "steady"))
+        .set_runner(
+            FakeRunner::new()
+                .with_output(&["sh", "-c", "flaky"], failure)
+                .with_stdout(&["sh", "-c", "steady"], "ok\n"),
+        );
+
+    let mut index = Index::new();
+    registry.collect_cached(&mut index);
+    let second = registry.collect_cached(&mut index);
+    assert!(!second.reports[0].cached);
+    assert!(matches!(second.reports[0].status, SourceStatus::Failed { code: Some(1), .. }));
+    assert!(second.reports[1].cached);
+    assert_eq!(second.reports[1].status, SourceStatus::Ok(1));
+}
+
+#[test]
+fn test_cli_verbose_reports_sources() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let not_a_dir = bin.path().join("file");
+    fs::write(&not_a_dir, "").unwrap();
+    let tool = bin.path().join("tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", bin.path().display(), not_a_dir.display());
+    let run = |args: &[&str]| {
+        bento(cache.path())
+            .args(args)
+            .env("PATH", &path)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", "/nonexistent")
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["--verbose", "tool"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("path"), "{stderr}");
+    assert!(stderr.contains("failed"), "{stderr}");
+    assert!(stderr.contains("Not a directory"), "{stderr}");
+    assert!(stderr.contains("homebrew   not installed"), "{stderr}");
+    // The other directories are still searched
+    assert!(String::from_utf8_lossy(&output.stdout).contains("tool (bin)"));
+
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Sources:"), "{stdout}");
+    assert!(stdout.contains("not installed"), "{stdout}");
+    assert!(run(&["tool"]).stderr.is_empty());
+}
+
+#[test]
+fn test_source_versions() {
+    let runner = Arc::new(
+        FakeRunner::new()
+            .with_stdout(&["cargo", "--version"], "cargo 1.80.0 (376290515 2024-07-16)\n")
+            .with_stdout(&["npm", "--version"], "10.8.2\n")
+            .with_stdout(&["pip", "--version"], "pip 24.0 from /usr/lib/python3/site-packages/pip (python 3.12)\n"),
+    );
+    let ctx = Context::with_runner(Duration::from_secs(5), runner);
+    assert_eq!(CargoSource.version(&ctx).as_deref(), Some("cargo 1.80.0 (376290515 2024-07-16)"));
+    assert_eq!(NpmSource.version(&ctx).as_deref(), Some("npm 10.8.2"));
+    assert_eq!(PipSource.version(&ctx).as_deref(), Some("pip 24.0"));
+    // A tool that cannot be run has no version, and that is not a failure
+    assert_eq!(GoSource.version(&ctx), None);
+    assert_eq!(AliasSource::new().version(&ctx), None);
+    assert_eq!(ctx.failure(), None);
+
+    struct Tool(bool);
+    impl Source for Tool {
+        fn name(&self) -> &str {
+            if self.0 { "installed" } else { "missing" }
+        }
+        fn category(&self) -> Category {
+            Category::Other("tool".to_string())
+        }
+        fn is_available(&self, _ctx: &Context) -> bool {
+            self.0
+        }
+        fn collect(&self, _ctx: &Context) -> Vec<BentoCommand> {
+            Vec::new()
+        }
+        fn version(&self, _ctx: &Context) -> Option<String> {
+            Some("tool 1.0".to_string())
+        }
+    }
+    let mut registry = Registry::new();
+    registry.register(Tool(true)).register(Tool(false));
+    let versions = registry.versions();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions["installed"], "tool 1.0");
+}
+
+#[test]
+fn test_check_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin = dir.path().join("bin");
+    let shared = dir.path().join("shared");
+    let file = dir.path().join("file");
+    fs::create_dir(&bin).unwrap();
+    fs::create_dir(&shared).unwrap();
+    fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+    fs::write(&file, "").unwrap();
+    let missing = dir.path().join("missing");
+
+    let path = format!(
+        "{bin}:{missing}::{file}:{bin}/:{shared}",
+        bin = bin.display(),
+        missing = missing.display(),
+        file = file.display(),
+        shared = shared.display(),
+    );
+    let issue = |entry: String, problem| PathIssue { entry, problem };
+    assert_eq!(
+        doctor::check_path(&path),
+        [
+            issue(missing.display().to_string(), PathProblem::Missing),
+            issue(String::new(), PathProblem::Empty),
+            issue(file.display().to_string(), PathProblem::NotADirectory),
+            issue(format!("{}/", bin.display()), PathProblem::Duplicate),
+            issue(shared.display().to_string(), PathProblem::WorldWritable),
+        ]
+    );
+    assert!(doctor::check_path(&bin.display().to_string()).is_empty());
+    assert_eq!(PathProblem::Missing.to_string(), "does not exist");
+}
+
+#[test]
+fn test_check_shell() {
+    let failed = Output {
+        status: ExitStatus::from_raw(1 << 8),
+        stdout: Vec::new(),
+        stderr: b"\nzsh: parse error near `}'\n".to_vec(),
+    };
+    let runner = Arc::new(
+        FakeRunner::new()
+            .with_stdout(&["/bin/bash", "-i", "-c", "true"], "")
+            .with_output(&["/bin/zsh", "-i", "-c", "true"], failed),
+    );
+    let ctx = Context::with_runner(Duration::from_secs(5), runner);
+    assert_eq!(doctor::check_shell("/bin/bash", &ctx), Ok(()));
+    assert_eq!(
+        doctor::check_shell("/bin/zsh", &ctx),
+        Err("exited with code 1: zsh: parse error near `}'".to_string())
+    );
+    assert!(doctor::check_shell("/bin/fish", &ctx).is_err());
+}
+
+#[test]
+fn test_cli_doctor() {
+    let bin = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let tool = bin.path().join("tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+    let run = |path: &str, shell: &str| {
+        bento(cache.path())
+            .arg("doctor")
+            .env("PATH", path)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", shell)
+            .env("NO_COLOR", "1")
+            .output()
+            .unwrap()
+    };
+
+    let missing = bin.path().join("missing");
+    let output = run(&format!("{}:{}", bin.path().display(), missing.display()), "/nonexistent");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert!(stdout.starts_with(&format!("bento {}", env!("CARGO_PKG_VERSION"))), "{stdout}");
+    assert!(stdout.contains("path       1 command"), "{stdout}");
+    assert!(stdout.contains("homebrew   not installed"), "{stdout}");
+    assert!(stdout.contains(&format!("{}: does not exist", missing.display())), "{stdout}");
+    // The shell is only run, and so only has to start, when configured to
+    assert!(stdout.contains("/nonexistent: "), "{stdout}");
+    assert!(stdout.contains("only used with interactive_shell"), "{stdout}");
+    assert!(stdout.contains("1 problem found"), "{stdout}");
+
+    let output = run(&bin.path().display().to_string(), "/bin/sh");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("1 directory"), "{stdout}");
+    assert!(stdout.contains("/bin/sh starts interactively"), "{stdout}");
+    assert!(stdout.contains("No problems found"), "{stdout}");
+
+    let config = cache.path().join("config.toml");
+    fs::write(&config, "interactive_shell = true\n").unwrap();
+    let output = bento(cache.path())
+        .arg("doctor")
+        .env("PATH", bin.path())
+        .env("BENTO_CONFIG", &config)
+        .env("XDG_CACHE_HOME", cache.path())
+        .env("SHELL", "/nonexistent")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    // The alias source fails to run the shell too
+    assert!(stdout.contains("alias      failed"), "{stdout}");
+    assert!(stdout.contains("2 problems found"), "{stdout}");
+}
+
+#[test]
+fn test_rc_definitions_parse() {
+    let script = r#"
+# alias commented='out'
+alias gs='git status' ll="ls -lah"  # trailing comment
+alias -g G='| grep'
+alias quote='echo '\''hi'\'''
+[ -x /usr/bin/nvim ] && alias vi=nvim
+alias gone=yes; unalias gone
+alias gs='git status -sb'
+
+mkcd() {
+    mkdir -p "$1" && cd "$1"
+}
+function serve {
+    if [ -n "$1" ]; then
+        python3 -m http.server "$1"
+    fi
+}
+up () { cd ..; }
+function clean() (rm -rf build)
+_private() { :; }
+greet()
+{
+    echo "} {"
+}
+after=1
+"#;
+    let definitions = RcDefinitions::parse(script);
+    let aliases: Vec<(&str, &str)> = definitions
+        .aliases
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        aliases,
+        [
+            ("gs", "git status -sb"),
+            ("ll", "ls -lah"),
+            ("G", "| grep"),
+            ("quote", "echo 'hi'"),
+            ("vi", "nvim"),
+        ]
+    );
+
+    let functions: Vec<&str> = definitions.functions.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(functions, ["mkcd", "serve", "up", "clean", "greet"]);
+    assert_eq!(definitions.functions[0].1, "mkcd() {\n    mkdir -p \"$1\" && cd \"$1\"\n}");
+    assert!(definitions.functions[1].1.ends_with("    fi\n}"));
+    assert_eq!(definitions.functions[4].1, "greet()\n{\n    echo \"} {\"\n}");
+}
+
+#[test]
+fn test_rc_definitions_follow_sourced_files() {
+    let home = tempfile::tempdir().unwrap();
+    let config = home.path().join(".config").join("sh");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        home.path().join(".bashrc"),
+        r#"CONF="$HOME/.config/sh"
+[ -f ~/.bash_aliases ] && . ~/.bash_aliases
+source "$CONF/functions.sh"
+source ~/.missing 2>/dev/null
+for f in ~/.bashrc.d/*; do source "$f"; done
+alias ll='ls -l'
+"#,
+    )
+    .unwrap();
+    fs::write(home.path().join(".bash_aliases"), "alias gs='git status'\nsource ~/.bashrc\n").unwrap();
+    fs::write(config.join("functions.sh"), "mkcd() { mkdir -p \"$1\"; }\n").unwrap();
+    fs::write(home.path().join(".zshrc"), "alias zonly=yes\n").unwrap();
+
+    let definitions = RcDefinitions::read(home.path(), Some("/bin/bash"));
+    let aliases: Vec<&str> = definitions.aliases.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(aliases, ["gs", "ll"]);
+    assert_eq!(definitions.functions.len(), 1);
+    assert_eq!(definitions.functions[0].0, "mkcd");
+    // Missing files are watched too, so creating them is noticed
+    assert!(definitions.files.contains(&home.path().join(".missing")));
+    assert!(definitions.files.contains(&config.join("functions.sh")));
+
+    // Any other shell reads both bash's and zsh's files
+    let definitions = RcDefinitions::read(home.path(), None);
+    assert!(definitions.aliases.iter().any(|(name, _)| name == "zonly"));
+}
+
+#[test]
+fn test_rc_definitions_zsh_frameworks() {
+    let home = tempfile::tempdir().unwrap();
+    let omz = home.path().join(".oh-my-zsh");
+    for dir in ["custom/plugins/mine", "plugins/git", "plugins/unused"] {
+        fs::create_dir_all(omz.join(dir)).unwrap();
+    }
+    fs::write(
+        home.path().join(".zshrc"),
+        "export ZSH=\"$HOME/.oh-my-zsh\"\nplugins=(\n  git\n  mine\n)\nsource $ZSH/oh-my-zsh.sh\n",
+    )
+    .unwrap();
+    fs::write(omz.join("plugins/git/git.plugin.zsh"), "alias gst='git status'\n").unwrap();
+    fs::write(omz.join("plugins/unused/unused.plugin.zsh"), "alias unused=yes\n").unwrap();
+    fs::write(omz.join("custom/plugins/mine/mine.plugin.zsh"), "alias mine=yes\n").unwrap();
+    fs::write(omz.join("custom/aliases.zsh"), "alias gst='git status -sb'\n").unwrap();
+
+    let prezto = home.path().join(".zprezto");
+    fs::create_dir_all(prezto.join("contrib/extra")).unwrap();
+    fs::write(
+        home.path().join(".zpreztorc"),
+        "zstyle ':prezto:load' pmodule \\\n  'environment' \\\n  'extra'\n",
+    )
+    .unwrap();
+    fs::write(prezto.join("contrib/extra/init.zsh"), "source \"${0:h}/alias.zsh\"\n").unwrap();
+    fs::write(prezto.join("contrib/extra/alias.zsh"), "extra() { echo extra; }\n").unwrap();
+
+    let definitions = RcDefinitions::read(home.path(), Some("/usr/bin/zsh"));
+    let aliases: Vec<(&str, &str)> = definitions
+        .aliases
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    // Custom files are loaded after plugins, so they win
+    assert_eq!(aliases, [("gst", "git status -sb"), ("mine", "yes")]);
+    assert_eq!(definitions.functions.len(), 1);
+    assert_eq!(definitions.functions[0].0, "extra");
+}
+
+#[test]
+fn test_rc_definitions_zdotdir_from_zshenv() {
+    let home = tempfile::tempdir().unwrap();
+    let zdotdir = home.path().join(".config").join("zsh");
+    fs::create_dir_all(&zdotdir).unwrap();
+    fs::write(home.path().join(".zshenv"), "export ZDOTDIR=~/.config/zsh\n").unwrap();
+    fs::write(zdotdir.join(".zprofile"), "alias profiled=yes\n").unwrap();
+    fs::write(zdotdir.join(".zshrc"), "alias moved=yes\n").unwrap();
+    // zsh no longer reads the files in the home directory
+    fs::write(home.path().join(".zshrc"), "alias stale=yes\n").unwrap();
+
+    let definitions = RcDefinitions::read(home.path(), Some("/bin/zsh"));
+    let aliases: Vec<&str> = definitions.aliases.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(aliases, ["profiled", "moved"]);
+    assert!(definitions.files.contains(&zdotdir.join(".zshrc")));
+}
+
+#[test]
+fn test_cli_reads_aliases_without_running_the_shell() {
+    let home = tempfile::tempdir().unwrap();
+    let cache = tempfile::tempdir().unwrap();
+    let bin = tempfile::tempdir().unwrap();
+    let shell = bin.path().join("bash");
+    let marker = bin.path().join("ran");
+    fs::write(&shell, format!("#!/bin/sh\ntouch {}\necho \"alias hidden='yes'\"\n", marker.display())).unwrap();
+    fs::set_permissions(&shell, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(home.path().join(".bashrc"), "alias gs='git status'\n").unwrap();
+    let config = home.path().join("config.toml");
+    let run = |config_text: &str| {
+        fs::write(&config, config_text).unwrap();
+        let output = bento(home.path())
+            .args(["--format", "tsv", "--alias", "--all", "--refresh"])
+            .env("BENTO_CONFIG", &config)
+            .env("XDG_CACHE_HOME", cache.path())
+            .env("SHELL", &shell)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run("");
+    assert!(stdout.contains("gs\talias"), "{stdout}");
+    assert!(!stdout.contains("hidden"), "{stdout}");
+    assert!(!marker.exists());
+
+    // The interactive shell is opt-in, and adds what the files don't show
+    let stdout = run("interactive_shell = true\n");
+    assert!(stdout.contains("gs\talias"), "{stdout}");
+    assert!(stdout.contains("hidden\talias"), "{stdout}");
+    assert!(marker.exists());
 }